@@ -0,0 +1,491 @@
+use std::rc::Rc;
+
+use crate::error::Location;
+
+/// A user defined `type` or `enum` block, or one of the builtin `string`, `number`,
+/// `bool`, `list<T>` and `map<K, V>` types.
+#[derive(Clone, Debug)]
+pub struct Type {
+    pub typename: String,
+    /// The `T` in `list<T>`, or the `K` and `V` in `map<K, V>`.
+    pub parameters: Vec<Type>,
+    /// The types named after the colon in `type Boss : Monster + Flying`, whose
+    /// fields this one inherits, in the order they're merged. Once resolved, each
+    /// parent names everything it inherits from among its own `parents`, once.
+    pub parents: Vec<Type>,
+    pub fields: Vec<Field>,
+    /// Written with a trailing `?`: a field of the type can be left out or set to `null`.
+    pub optional: bool,
+    /// The limits in parentheses after a builtin type, like the `0..=1000` in `number(0..=1000)`.
+    pub constraints: Vec<Constraint>,
+    /// The names an `enum` declares, in order. `None` for anything that isn't an enum.
+    pub variants: Option<Vec<Variant>>,
+    pub location: Location,
+    /// The `///` comment in front of the declaration.
+    pub doc: Option<String>,
+}
+
+/// A limit on the values a field of a builtin type accepts: `number(0..=1000)`,
+/// `string(len 1..32)` or `string(match "^[a-z_]+$")`.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Constraint {
+    /// The number is within the bounds.
+    Range(Bounds),
+    /// The number of characters in a string, or of elements in a list or map, is within the bounds.
+    Length(Bounds),
+    /// The string matches the regular expression.
+    Pattern(String),
+}
+
+/// `min..max` or `min..=max`. Either end may be left out.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Bounds {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Whether `max` itself is in range.
+    pub inclusive: bool,
+}
+
+/// One of the names declared by an `enum`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Variant {
+    pub name: String,
+    pub location: Location,
+    pub doc: Option<String>,
+}
+
+/// The right hand side of a field: a single literal, a `[...]` list of values,
+/// a `{ key = value, ... }` map, a nested `name { ... }` object or the name of
+/// an enum variant. A field that hasn't been given a value, or was set to `null`, holds `Null`.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    List(Vec<Value>),
+    Map(Vec<Field>),
+    Object(Box<Object>),
+    /// A bare name like `Fire`. `index` is its position in the enum, known once
+    /// the field's type has been resolved.
+    Variant {
+        name: String,
+        index: Option<usize>,
+    },
+    #[default]
+    Null,
+}
+
+/// A `name = value` pair. Fields belong to a type, an object or the top level of a file.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Field {
+    pub identifier: String,
+    pub identifier_type: Type,
+    pub initialized: bool,
+    pub value: Value,
+    pub location: Location,
+    pub value_location: Location,
+    pub doc: Option<String>,
+}
+
+/// A named block of fields, optionally of a user defined type. Objects are
+/// declared at the top level of a file or nested as the value of a field.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Object {
+    pub object_name: String,
+    /// The types and earlier objects named after the colon in `Lich : Undead + Caster`,
+    /// or by the label in front of the object, in the order they're merged.
+    pub parents: Vec<Type>,
+    /// Once resolved, the type the object's fields are checked against. With
+    /// more than one type among its parents, a nameless type combining them.
+    pub object_type: Option<Type>,
+    /// Once resolved, the objects among its parents, with their own fields resolved.
+    /// Shared with every other object that inherits from them.
+    pub prototypes: Vec<Rc<Object>>,
+    pub fields: Vec<Field>,
+    pub location: Location,
+    pub doc: Option<String>,
+}
+
+/// A `use` directive: `use Name from "file.cfg"`, `use { Name, Other as Alias } from "file.cfg"`,
+/// `use * from "file.cfg"` or `use "file.cfg" as name`.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Import {
+    /// The names listed, empty for `use *` and for a namespace.
+    pub names: Vec<ImportedName>,
+    /// Whether this is `use *`, which brings in every type and object the file exports.
+    pub wildcard: bool,
+    /// The name the file's exports are qualified with, as in `items::Item`, for
+    /// `use "items.cfg"`. Without `as` it's the name of the file.
+    pub namespace: Option<String>,
+    /// The file path as written in the directive.
+    pub path: String,
+    pub location: Location,
+}
+
+/// One of the names a `use` directive lists.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ImportedName {
+    /// The name in the file it's imported from.
+    pub name: String,
+    /// The name in the importing file: the alias in `use Name as Alias`, `name` otherwise.
+    pub local_name: String,
+    pub location: Location,
+}
+
+/// Everything declared in a single file, before types are applied to objects.
+#[derive(Clone, Debug)]
+pub struct ParsedFile {
+    /// The path the file was parsed with, the one its locations point into.
+    pub path: String,
+    pub imports: Vec<Import>,
+    pub user_types: Vec<Type>,
+    pub user_objects: Vec<Object>,
+    pub user_fields: Vec<Field>,
+    /// The names marked with `export`, declared in the file or listed by one of
+    /// its `use` directives. A file that marks none exports everything it declares.
+    pub exports: Vec<String>,
+}
+
+/// A resolved file: every object carries the complete set of fields of its type.
+#[derive(Clone, Debug)]
+pub struct Document {
+    pub fields: Vec<Field>,
+    pub objects: Vec<Object>,
+}
+
+impl Field {
+    pub fn new() -> Field {
+        Field {
+            identifier: String::new(),
+            identifier_type: Type::new(),
+            initialized: false,
+            value: Value::Null,
+            location: Location::default(),
+            value_location: Location::default(),
+            doc: None,
+        }
+    }
+}
+
+impl Default for Field {
+    fn default() -> Self {
+        Field::new()
+    }
+}
+
+impl Type {
+    pub fn new() -> Type {
+        Type {
+            typename: String::new(),
+            parameters: Vec::new(),
+            parents: Vec::new(),
+            fields: Vec::new(),
+            optional: false,
+            constraints: Vec::new(),
+            variants: None,
+            location: Location::default(),
+            doc: None,
+        }
+    }
+    pub fn new_string_type() -> Type {
+        Type {
+            typename: String::from("string"),
+            ..Type::new()
+        }
+    }
+    pub fn new_bool_type() -> Type {
+        Type {
+            typename: String::from("bool"),
+            ..Type::new()
+        }
+    }
+    pub fn new_number_type() -> Type {
+        Type {
+            typename: String::from("number"),
+            ..Type::new()
+        }
+    }
+    pub fn new_list_type(element_type: Type) -> Type {
+        Type {
+            typename: String::from("list"),
+            parameters: vec![element_type],
+            ..Type::new()
+        }
+    }
+    pub fn new_map_type(key_type: Type, value_type: Type) -> Type {
+        Type {
+            typename: String::from("map"),
+            parameters: vec![key_type, value_type],
+            ..Type::new()
+        }
+    }
+}
+
+impl Type {
+    /// The types this one inherits from, depth first in the order they're merged.
+    pub fn ancestors(&self) -> Vec<String> {
+        let mut ancestors = vec![];
+        for parent in &self.parents {
+            ancestors.push(parent.typename.clone());
+            ancestors.extend(parent.ancestors());
+        }
+        ancestors
+    }
+
+    /// Whether this is one of the types the language comes with, rather than a user defined one.
+    pub fn is_builtin(&self) -> bool {
+        matches!(
+            self.typename.as_str(),
+            "string" | "number" | "bool" | "list" | "map"
+        )
+    }
+
+    /// Whether this type was declared with `enum`.
+    pub fn is_enum(&self) -> bool {
+        self.variants.is_some()
+    }
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.typename)?;
+        if !self.parameters.is_empty() {
+            let parameters: Vec<String> = self.parameters.iter().map(Type::to_string).collect();
+            write!(f, "<{}>", parameters.join(", "))?;
+        }
+        if self.optional {
+            write!(f, "?")?;
+        }
+        Ok(())
+    }
+}
+
+impl Bounds {
+    pub fn contains(&self, number: f64) -> bool {
+        let above_min = self.min.is_none_or(|min| number >= min);
+        let below_max = match self.max {
+            Some(max) if self.inclusive => number <= max,
+            Some(max) => number < max,
+            None => true,
+        };
+        above_min && below_max
+    }
+}
+
+impl std::fmt::Display for Bounds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(min) = self.min {
+            write!(f, "{}", min)?;
+        }
+        write!(f, "{}", if self.inclusive { "..=" } else { ".." })?;
+        if let Some(max) = self.max {
+            write!(f, "{}", max)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Constraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constraint::Range(bounds) => write!(f, "{}", bounds),
+            Constraint::Length(bounds) => write!(f, "len {}", bounds),
+            Constraint::Pattern(pattern) => write!(f, "match {:?}", pattern),
+        }
+    }
+}
+
+impl Default for Type {
+    fn default() -> Self {
+        Type::new()
+    }
+}
+
+impl std::cmp::PartialEq for Type {
+    fn eq(&self, other: &Self) -> bool {
+        self.typename == other.typename && self.parameters == other.parameters
+    }
+}
+
+impl Value {
+    /// The builtin type a value has on its own. Lists and maps take the type of
+    /// their first element.
+    pub fn literal_type(&self) -> Type {
+        match self {
+            Value::String(_) => Type::new_string_type(),
+            Value::Integer(_) | Value::Float(_) => Type::new_number_type(),
+            Value::Bool(_) => Type::new_bool_type(),
+            Value::List(elements) => match elements.first() {
+                Some(element) => Type::new_list_type(element.literal_type()),
+                None => Type {
+                    typename: String::from("list"),
+                    ..Type::new()
+                },
+            },
+            Value::Object(_) => Type {
+                typename: String::from("object"),
+                ..Type::new()
+            },
+            Value::Map(entries) => match entries.first() {
+                Some(entry) => {
+                    Type::new_map_type(Type::new_string_type(), entry.value.literal_type())
+                }
+                None => Type {
+                    typename: String::from("map"),
+                    ..Type::new()
+                },
+            },
+            Value::Variant { .. } => Type {
+                typename: String::from("variant"),
+                ..Type::new()
+            },
+            Value::Null => Type {
+                typename: String::from("null"),
+                ..Type::new()
+            },
+        }
+    }
+
+    /// The value of an integer or a float, as a float.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Integer(integer) => Some(*integer as f64),
+            Value::Float(float) => Some(*float),
+            _ => None,
+        }
+    }
+
+    /// The named values inside a map or a nested object.
+    pub fn entries(&self) -> Option<&[Field]> {
+        match self {
+            Value::Map(entries) => Some(entries),
+            Value::Object(object) => Some(&object.fields),
+            _ => None,
+        }
+    }
+}
+
+impl Object {
+    pub fn new() -> Object {
+        Object {
+            object_name: String::new(),
+            parents: Vec::new(),
+            object_type: None,
+            prototypes: Vec::new(),
+            fields: Vec::new(),
+            location: Location::default(),
+            doc: None,
+        }
+    }
+}
+
+impl Object {
+    /// What this object inherits from: each of its prototype objects followed by
+    /// what that one inherits from, then its type and that type's ancestors.
+    /// Every name shows up once. Complete once the object is resolved.
+    pub fn inheritance_chain(&self) -> Vec<String> {
+        let mut chain = vec![];
+        self.collect_ancestors(&mut chain, &mut Vec::new());
+        let mut unique: Vec<String> = vec![];
+        for name in chain {
+            if !name.is_empty() && !unique.contains(&name) {
+                unique.push(name);
+            }
+        }
+        unique
+    }
+
+    /// Appends what this object inherits from to `chain`. `visited` holds the
+    /// prototypes already walked, so one shared by several parents is walked once.
+    fn collect_ancestors(&self, chain: &mut Vec<String>, visited: &mut Vec<*const Object>) {
+        for prototype in &self.prototypes {
+            if visited.contains(&Rc::as_ptr(prototype)) {
+                continue;
+            }
+            visited.push(Rc::as_ptr(prototype));
+            chain.push(prototype.object_name.clone());
+            prototype.collect_ancestors(chain, visited);
+        }
+        if let Some(object_type) = &self.object_type {
+            chain.push(object_type.typename.clone());
+            chain.extend(object_type.ancestors());
+        }
+    }
+}
+
+impl Default for Object {
+    fn default() -> Self {
+        Object::new()
+    }
+}
+
+impl ParsedFile {
+    pub fn new() -> ParsedFile {
+        ParsedFile {
+            path: String::new(),
+            imports: Vec::new(),
+            user_types: Vec::new(),
+            user_objects: Vec::new(),
+            user_fields: Vec::new(),
+            exports: Vec::new(),
+        }
+    }
+}
+
+impl ParsedFile {
+    /// The `use` directive that lists `local_name`, and the name it imports.
+    pub fn imported_name(&self, local_name: &str) -> Option<(&Import, &ImportedName)> {
+        self.imports.iter().find_map(|import| {
+            import
+                .names
+                .iter()
+                .find(|imported| imported.local_name == local_name)
+                .map(|imported| (import, imported))
+        })
+    }
+
+    /// The `use` directive that makes `namespace` the name of a file.
+    pub fn namespace(&self, namespace: &str) -> Option<&Import> {
+        self.imports
+            .iter()
+            .find(|import| import.namespace.as_deref() == Some(namespace))
+    }
+
+    /// Whether the file declares a type, an object or a top level field called `name` itself.
+    pub fn declares(&self, name: &str) -> bool {
+        self.declared_at(name).is_some()
+    }
+
+    /// Where the file declares the type, object or top level field called `name`.
+    pub fn declared_at(&self, name: &str) -> Option<&Location> {
+        let types = self
+            .user_types
+            .iter()
+            .map(|usertype| (&usertype.typename, &usertype.location));
+        let objects = self
+            .user_objects
+            .iter()
+            .map(|object| (&object.object_name, &object.location));
+        let fields = self
+            .user_fields
+            .iter()
+            .map(|field| (&field.identifier, &field.location));
+        types
+            .chain(objects)
+            .chain(fields)
+            .find(|(declared, _)| *declared == name)
+            .map(|(_, location)| location)
+    }
+
+    /// Whether other files can import `name` from this one.
+    pub fn exports_name(&self, name: &str) -> bool {
+        self.exports.is_empty() || self.exports.iter().any(|exported| exported == name)
+    }
+}
+
+impl Default for ParsedFile {
+    fn default() -> Self {
+        ParsedFile::new()
+    }
+}
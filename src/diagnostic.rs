@@ -0,0 +1,198 @@
+use crate::error::{ConfigError, Location};
+
+/// The text of the files errors point into. Files that weren't added are read
+/// from disk when an error needs them.
+#[derive(Clone, Debug, Default)]
+pub struct Sources {
+    files: Vec<(String, String)>,
+}
+
+impl Sources {
+    pub fn new() -> Sources {
+        Sources { files: Vec::new() }
+    }
+
+    /// Registers the contents of a file that isn't on disk, e.g. one parsed with `parse_str`.
+    pub fn add(&mut self, path: &str, contents: &str) {
+        self.files.push((path.to_string(), contents.to_string()));
+    }
+
+    fn get(&self, path: &str) -> Option<String> {
+        match self.files.iter().find(|(file_path, _)| file_path == path) {
+            Some((_, contents)) => Some(contents.clone()),
+            None => std::fs::read_to_string(path).ok(),
+        }
+    }
+}
+
+/// Number of single character edits (insertions, deletions, substitutions and
+/// swaps of neighbouring characters) needed to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    distances[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+/// Picks the candidate closest to `name`, if any is close enough to be a likely typo.
+pub(crate) fn closest_match<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    // Replacing the whole of a short name isn't a typo, so one letter names match nothing.
+    let length = name.chars().count();
+    let limit = (length / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit && *distance < length)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Appends ` --> location` and, when the line can be found, the quoted line
+/// with the location underlined.
+fn render_snippet(output: &mut String, location: &Location, sources: &Sources) {
+    output.push_str(&format!(" --> {}\n", location));
+    if location.line == 0 {
+        return;
+    }
+    let text = match sources.get(&location.path) {
+        Some(contents) => match contents.lines().nth(location.line - 1) {
+            Some(text) => text.to_string(),
+            None => return,
+        },
+        None => return,
+    };
+
+    // Underline at least one character, and never past the end of the line.
+    let mut width = 0;
+    let mut covered = 0;
+    for character in text.chars().skip(location.column - 1) {
+        if width > 0 && covered >= location.length {
+            break;
+        }
+        covered += character.len_utf8();
+        width += 1;
+    }
+
+    // Keep tabs in the padding so the carets line up with the quoted text.
+    let padding: String = text
+        .chars()
+        .take(location.column - 1)
+        .map(|character| if character == '\t' { '\t' } else { ' ' })
+        .collect();
+    let gutter = " ".repeat(location.line.to_string().len());
+    output.push_str(&format!("{} |\n", gutter));
+    output.push_str(&format!("{} | {}\n", location.line, text));
+    output.push_str(&format!("{} | {}{}\n", gutter, padding, "^".repeat(width)));
+}
+
+/// Renders an error the way rustc does: the message, the offending line with
+/// the location underlined, then any notes and a suggested fix.
+pub fn render(error: &ConfigError, sources: &Sources) -> String {
+    let mut output = format!("error: {}\n", error.message());
+    render_snippet(&mut output, error.location(), sources);
+    for note in error.notes() {
+        output.push_str(&format!("note: {}\n", note.message));
+        render_snippet(&mut output, &note.location, sources);
+    }
+    if let Some(help) = error.help() {
+        output.push_str(&format!("help: {}\n", help));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_first_error(source: &str) -> String {
+        let mut sources = Sources::new();
+        sources.add("test.cfg", source);
+        let errors = crate::parse_partial(source, "test.cfg").1;
+        render(&errors[0], &sources)
+    }
+
+    #[test]
+    fn edit_distance_counts_single_character_edits() {
+        assert_eq!(edit_distance("Monster", "Monster"), 0);
+        assert_eq!(edit_distance("Monster", "Monstr"), 1);
+        assert_eq!(edit_distance("Monster", "Monsters"), 1);
+        assert_eq!(edit_distance("Monster", "Mongter"), 1);
+        assert_eq!(edit_distance("Monster", "Mosnter"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("é", "e"), 1);
+    }
+
+    #[test]
+    fn closest_match_needs_a_likely_typo() {
+        let candidates = ["Monster", "Goblin", "T"];
+        assert_eq!(closest_match("Monstr", candidates), Some("Monster"));
+        assert_eq!(closest_match("Gobiln", candidates), Some("Goblin"));
+        assert_eq!(closest_match("Dragon", candidates), None);
+        assert_eq!(closest_match("x", candidates), None);
+        assert_eq!(closest_match("T", candidates), None);
+    }
+
+    #[test]
+    fn render_underlines_the_location_with_notes_and_help() {
+        assert_eq!(
+            render_first_error("type Monster { }\nGoblin : Monstr { }\n"),
+            "error: type 'Monstr' not found\n\
+             \x20--> test.cfg:2:10\n\
+             \x20 |\n\
+             2 | Goblin : Monstr { }\n\
+             \x20 |          ^^^^^^\n\
+             note: 'Monster' is declared here\n\
+             \x20--> test.cfg:1:6\n\
+             \x20 |\n\
+             1 | type Monster { }\n\
+             \x20 |      ^^^^^^^\n\
+             help: did you mean 'Monster'?\n"
+        );
+    }
+
+    #[test]
+    fn carets_line_up_after_tabs_and_multi_byte_characters() {
+        assert_eq!(
+            render_first_error("\tname = \"héllo\" @\n"),
+            "error: unexpected character '@'\n\
+             \x20--> test.cfg:1:17\n\
+             \x20 |\n\
+             1 | \tname = \"héllo\" @\n\
+             \x20 | \t               ^\n"
+        );
+        assert_eq!(
+            render_first_error("type Monster { }\nGöblin : Mönstr { }\n"),
+            "error: type 'Mönstr' not found\n\
+             \x20--> test.cfg:2:10\n\
+             \x20 |\n\
+             2 | Göblin : Mönstr { }\n\
+             \x20 |          ^^^^^^\n\
+             note: 'Monster' is declared here\n\
+             \x20--> test.cfg:1:6\n\
+             \x20 |\n\
+             1 | type Monster { }\n\
+             \x20 |      ^^^^^^^\n\
+             help: did you mean 'Monster'?\n"
+        );
+    }
+}
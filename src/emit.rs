@@ -0,0 +1,144 @@
+use crate::ast::{Field, Object, Value};
+
+/// Choices about how a document is written out as JSON.
+#[derive(Clone, Debug, Default)]
+pub struct JsonOptions {
+    /// Write enum variants as their position in the enum, starting at 0,
+    /// instead of as their name.
+    pub enums_as_integers: bool,
+}
+
+impl JsonOptions {
+    pub fn new() -> JsonOptions {
+        JsonOptions {
+            enums_as_integers: false,
+        }
+    }
+}
+
+/// Something that becomes a member of a JSON object: a value, or the fields of
+/// a top level object.
+enum Member<'a> {
+    Value(&'a Value),
+    Fields(&'a [Field]),
+}
+
+/// Writes `value` as JSON. Floats always keep a fraction or an exponent, so
+/// `1.0` doesn't come out as the integer `1`.
+fn write_value(output: &mut String, value: &Value, options: &JsonOptions) {
+    match value {
+        Value::String(string) => output.push_str(&json::stringify(string.as_str())),
+        Value::Integer(integer) => output.push_str(&integer.to_string()),
+        Value::Float(float) => output.push_str(&format!("{:?}", float)),
+        Value::Bool(boolean) => output.push_str(&boolean.to_string()),
+        Value::List(elements) => {
+            output.push('[');
+            for (index, element) in elements.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_value(output, element, options);
+            }
+            output.push(']');
+        }
+        Value::Map(entries) => write_fields(output, entries, options),
+        Value::Object(object) => write_fields(output, &object.fields, options),
+        Value::Variant {
+            index: Some(index), ..
+        } if options.enums_as_integers => output.push_str(&index.to_string()),
+        Value::Variant { name, .. } => output.push_str(&json::stringify(name.as_str())),
+        Value::Null => output.push_str("null"),
+    }
+}
+
+fn write_fields(output: &mut String, fields: &[Field], options: &JsonOptions) {
+    let members: Vec<(&str, Member)> = fields
+        .iter()
+        .map(|field| (field.identifier.as_str(), Member::Value(&field.value)))
+        .collect();
+    write_object(output, members, options);
+}
+
+/// Writes a JSON object. A name that shows up twice keeps its first position and its last value.
+fn write_object(output: &mut String, members: Vec<(&str, Member)>, options: &JsonOptions) {
+    let mut unique: Vec<(&str, Member)> = Vec::new();
+    for (name, member) in members {
+        match unique.iter_mut().find(|(existing, _)| *existing == name) {
+            Some(existing) => existing.1 = member,
+            None => unique.push((name, member)),
+        }
+    }
+
+    output.push('{');
+    for (index, (name, member)) in unique.iter().enumerate() {
+        if index > 0 {
+            output.push(',');
+        }
+        output.push_str(&json::stringify(*name));
+        output.push(':');
+        match member {
+            Member::Value(value) => write_value(output, value, options),
+            Member::Fields(fields) => write_fields(output, fields, options),
+        }
+    }
+    output.push('}');
+}
+
+pub(crate) fn emit_json(
+    typed_objects: &[Object],
+    user_fields: &[Field],
+    options: &JsonOptions,
+) -> String {
+    let mut members: Vec<(&str, Member)> = user_fields
+        .iter()
+        .map(|field| (field.identifier.as_str(), Member::Value(&field.value)))
+        .collect();
+    for object in typed_objects {
+        members.push((object.object_name.as_str(), Member::Fields(&object.fields)));
+    }
+    let mut output = String::new();
+    write_object(&mut output, members, options);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    fn json(source: &str) -> String {
+        let parsed = crate::parse_str(source).unwrap();
+        crate::to_json(&crate::resolve(&parsed).unwrap())
+    }
+
+    #[test]
+    fn lists_are_emitted_as_arrays() {
+        assert_eq!(
+            json(
+                "tags = [\"undead\", \"night\"]\nempty = []\nGoblin { drops = [[1, 2], [true]] }\n"
+            ),
+            r#"{"tags":["undead","night"],"empty":[],"Goblin":{"drops":[[1,2],[true]]}}"#
+        );
+    }
+
+    #[test]
+    fn maps_are_emitted_as_objects() {
+        assert_eq!(
+            json("type M { r: map<string, number> = {} }\nG : M { r = { fire = 2, ice = 1 } }\nH : M { }\n"),
+            r#"{"G":{"r":{"fire":2,"ice":1}},"H":{"r":{}}}"#
+        );
+    }
+
+    #[test]
+    fn nested_objects_are_emitted_inside_their_object() {
+        assert_eq!(
+            json("Goblin {\n  stats {\n    hp = 10\n    inner { x = \"y\" }\n  }\n  loot = { min = 1 }\n}\n"),
+            r#"{"Goblin":{"stats":{"hp":10,"inner":{"x":"y"}},"loot":{"min":1}}}"#
+        );
+    }
+
+    #[test]
+    fn integers_and_floats_keep_their_kind() {
+        assert_eq!(
+            json("a = 1\nb = 1.0\nc = -3\nd = 2.5e-3\ne = 1e3\nf = 0xff\n"),
+            r#"{"a":1,"b":1.0,"c":-3,"d":0.0025,"e":1000.0,"f":255}"#
+        );
+    }
+}
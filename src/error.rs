@@ -0,0 +1,463 @@
+use crate::lexer::Span;
+
+/// A position in a config file. Lines and columns start at 1; a line of 0
+/// refers to the file as a whole. `length` is the number of bytes the
+/// location covers, used to underline it in rendered diagnostics.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Location {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+}
+
+/// A declared name that's close to one that couldn't be found.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Suggestion {
+    pub name: String,
+    pub location: Location,
+}
+
+/// Secondary information attached to an error, pointing at a related place in the source.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Note {
+    pub message: String,
+    pub location: Location,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The text could not be split into tokens, e.g. an unterminated string.
+    Lex { message: String, location: Location },
+    /// A token showed up where the grammar doesn't allow it.
+    Syntax {
+        expected: String,
+        found: String,
+        location: Location,
+    },
+    /// A label or type assignment names a type that doesn't exist.
+    UnknownType {
+        typename: String,
+        location: Location,
+        suggestion: Option<Suggestion>,
+    },
+    /// A `use` directive names something the file it imports from doesn't declare.
+    UnknownImport {
+        name: String,
+        path: String,
+        location: Location,
+        suggestion: Option<Suggestion>,
+    },
+    /// A `use` directive or a qualified name like `items::Item` names something the
+    /// file declares without `export`, in a file that exports other names.
+    PrivateImport {
+        name: String,
+        path: String,
+        location: Location,
+        declared_at: Location,
+    },
+    /// A qualified name like `items::Item` starts with a namespace no `use` directive gives.
+    UnknownNamespace {
+        namespace: String,
+        location: Location,
+        suggestion: Option<Suggestion>,
+    },
+    /// A name that isn't declared in the file comes from more than one `use *`.
+    /// `paths` are the files it could come from, `imported_at` their `use` directives.
+    AmbiguousName {
+        name: String,
+        paths: Vec<String>,
+        location: Location,
+        imported_at: Vec<Location>,
+    },
+    /// Two `use` directives bring in different things under the same name.
+    DuplicateImport {
+        name: String,
+        location: Location,
+        first_location: Location,
+    },
+    /// The file a `use` directive names isn't in any of the places it was looked for.
+    UnresolvedImport {
+        path: String,
+        tried: Vec<String>,
+        location: Location,
+    },
+    /// A top level field's value names the field itself, directly or through other fields.
+    ReferenceCycle {
+        chain: Vec<String>,
+        location: Location,
+    },
+    /// A file imports from itself, directly or through other files.
+    ImportCycle {
+        cycle: Vec<String>,
+        location: Location,
+    },
+    /// An object doesn't set a field its type leaves uninitialized.
+    MissingField {
+        field: String,
+        object: String,
+        location: Location,
+        declared_at: Location,
+        suggestion: Option<Suggestion>,
+    },
+    /// A value doesn't have the type its field is declared with.
+    TypeMismatch {
+        field: String,
+        expected: String,
+        found: String,
+        location: Location,
+        declared_at: Location,
+    },
+    /// A value is outside what a constraint on its field's type allows, e.g. `life = -5`
+    /// for `life: number(0..=1000)`. `object` is the object, or the type whose default it is.
+    ConstraintViolation {
+        field: String,
+        object: String,
+        requirement: String,
+        found: String,
+        location: Location,
+        declared_at: Location,
+    },
+    /// Two parents give a field different defaults and the child doesn't set it
+    /// itself to settle which one it gets.
+    ConflictingDefaults {
+        field: String,
+        child: String,
+        first_parent: String,
+        first_location: Location,
+        second_parent: String,
+        second_location: Location,
+        location: Location,
+    },
+    /// A field of an enum type is set to a name the enum doesn't declare.
+    UnknownVariant {
+        variant: String,
+        enum_name: String,
+        variants: Vec<String>,
+        location: Location,
+        suggestion: Option<Suggestion>,
+    },
+    /// A bare name that isn't a top level field is the value of a field without an enum type.
+    UntypedVariant { variant: String, location: Location },
+    /// A type is its own ancestor, e.g. `type A : B` and `type B : A`.
+    InheritanceCycle {
+        chain: Vec<String>,
+        location: Location,
+    },
+    /// A file could not be read.
+    Io {
+        path: String,
+        error: std::io::Error,
+        location: Location,
+    },
+}
+
+impl Location {
+    pub fn new(path: &str, line: usize, column: usize) -> Location {
+        Location {
+            path: path.to_string(),
+            line,
+            column,
+            length: 0,
+        }
+    }
+
+    pub fn from_span(path: &str, span: Span) -> Location {
+        Location {
+            length: span.length,
+            ..Location::new(path, span.line, span.column)
+        }
+    }
+}
+
+impl ConfigError {
+    pub fn location(&self) -> &Location {
+        match self {
+            ConfigError::Lex { location, .. }
+            | ConfigError::Syntax { location, .. }
+            | ConfigError::UnknownType { location, .. }
+            | ConfigError::UnknownImport { location, .. }
+            | ConfigError::PrivateImport { location, .. }
+            | ConfigError::UnknownNamespace { location, .. }
+            | ConfigError::AmbiguousName { location, .. }
+            | ConfigError::DuplicateImport { location, .. }
+            | ConfigError::UnresolvedImport { location, .. }
+            | ConfigError::ImportCycle { location, .. }
+            | ConfigError::ReferenceCycle { location, .. }
+            | ConfigError::MissingField { location, .. }
+            | ConfigError::TypeMismatch { location, .. }
+            | ConfigError::ConstraintViolation { location, .. }
+            | ConfigError::ConflictingDefaults { location, .. }
+            | ConfigError::UnknownVariant { location, .. }
+            | ConfigError::UntypedVariant { location, .. }
+            | ConfigError::InheritanceCycle { location, .. }
+            | ConfigError::Io { location, .. } => location,
+        }
+    }
+
+    /// The error without its location.
+    pub fn message(&self) -> String {
+        match self {
+            ConfigError::Lex { message, .. } => message.clone(),
+            ConfigError::Syntax {
+                expected, found, ..
+            } => format!("expected {}, found {}", expected, found),
+            ConfigError::UnknownType { typename, .. } => format!("type '{}' not found", typename),
+            ConfigError::UnknownImport { name, path, .. } => {
+                format!("'{}' is not declared in '{}'", name, path)
+            }
+            ConfigError::PrivateImport { name, path, .. } => {
+                format!("'{}' is private to '{}'", name, path)
+            }
+            ConfigError::UnknownNamespace { namespace, .. } => {
+                format!("namespace '{}' not found", namespace)
+            }
+            ConfigError::AmbiguousName { name, paths, .. } => {
+                let quoted: Vec<String> = paths.iter().map(|path| format!("'{}'", path)).collect();
+                format!("'{}' is ambiguous: {} declare it", name, quoted.join(" and "))
+            }
+            ConfigError::DuplicateImport { name, .. } => {
+                format!("'{}' is imported more than once", name)
+            }
+            ConfigError::UnresolvedImport { path, .. } => format!("could not find '{}'", path),
+            ConfigError::ReferenceCycle { chain, .. } => format!(
+                "field '{}' refers to itself: {}",
+                chain[0],
+                chain.join(" -> ")
+            ),
+            ConfigError::ImportCycle { cycle, .. } => {
+                format!("'{}' imports itself: {}", cycle[0], cycle.join(" -> "))
+            }
+            ConfigError::MissingField { field, object, .. } => format!(
+                "field '{}' must be initialized by object '{}'",
+                field, object
+            ),
+            ConfigError::TypeMismatch {
+                expected, found, ..
+            } => format!(
+                "mismatched types: expected '{}', found '{}'",
+                expected, found
+            ),
+            ConfigError::ConstraintViolation {
+                field,
+                object,
+                requirement,
+                found,
+                ..
+            } => format!(
+                "field '{}' of '{}' must {}, found {}",
+                field, object, requirement, found
+            ),
+            ConfigError::ConflictingDefaults {
+                field,
+                first_parent,
+                second_parent,
+                ..
+            } => format!(
+                "'{}' and '{}' give field '{}' different defaults",
+                first_parent, second_parent, field
+            ),
+            ConfigError::UnknownVariant {
+                variant, enum_name, ..
+            } => format!("'{}' is not a variant of enum '{}'", variant, enum_name),
+            ConfigError::UntypedVariant { variant, .. } => format!(
+                "cannot find '{}': no top level field has that name, and the field doesn't have an enum type",
+                variant
+            ),
+            ConfigError::InheritanceCycle { chain, .. } => format!(
+                "type '{}' inherits from itself: {}",
+                chain[0],
+                chain.join(" : ")
+            ),
+            ConfigError::Io { path, error, .. } => format!("could not read '{}': {}", path, error),
+        }
+    }
+
+    /// Related places in the source that help explain the error.
+    pub fn notes(&self) -> Vec<Note> {
+        let mut notes = vec![];
+        match self {
+            ConfigError::UnknownType {
+                suggestion: Some(suggestion),
+                ..
+            }
+            | ConfigError::UnknownVariant {
+                suggestion: Some(suggestion),
+                ..
+            }
+            | ConfigError::UnknownImport {
+                suggestion: Some(suggestion),
+                ..
+            }
+            | ConfigError::UnknownNamespace {
+                suggestion: Some(suggestion),
+                ..
+            } => notes.push(Note {
+                message: format!("'{}' is declared here", suggestion.name),
+                location: suggestion.location.clone(),
+            }),
+            ConfigError::PrivateImport {
+                name, declared_at, ..
+            } => notes.push(Note {
+                message: format!("'{}' is declared here without 'export'", name),
+                location: declared_at.clone(),
+            }),
+            ConfigError::AmbiguousName {
+                name,
+                paths,
+                imported_at,
+                ..
+            } => {
+                for (path, location) in paths.iter().zip(imported_at) {
+                    notes.push(Note {
+                        message: format!("'{}' is imported from '{}' here", name, path),
+                        location: location.clone(),
+                    });
+                }
+            }
+            ConfigError::DuplicateImport {
+                name,
+                first_location,
+                ..
+            } => notes.push(Note {
+                message: format!("'{}' is first imported here", name),
+                location: first_location.clone(),
+            }),
+            ConfigError::MissingField {
+                field,
+                declared_at,
+                suggestion,
+                ..
+            } => {
+                notes.push(Note {
+                    message: format!("'{}' is declared here without a default", field),
+                    location: declared_at.clone(),
+                });
+                if let Some(suggestion) = suggestion {
+                    notes.push(Note {
+                        message: format!("'{}' is set here", suggestion.name),
+                        location: suggestion.location.clone(),
+                    });
+                }
+            }
+            ConfigError::TypeMismatch {
+                field, declared_at, ..
+            } => notes.push(Note {
+                message: format!("'{}' is declared here", field),
+                location: declared_at.clone(),
+            }),
+            ConfigError::ConstraintViolation { declared_at, .. } => notes.push(Note {
+                message: String::from("the constraint is declared here"),
+                location: declared_at.clone(),
+            }),
+            ConfigError::ConflictingDefaults {
+                first_parent,
+                first_location,
+                second_parent,
+                second_location,
+                ..
+            } => {
+                notes.push(Note {
+                    message: format!("'{}' gets its default here", first_parent),
+                    location: first_location.clone(),
+                });
+                notes.push(Note {
+                    message: format!("'{}' gets its default here", second_parent),
+                    location: second_location.clone(),
+                });
+            }
+            _ => (),
+        }
+        notes
+    }
+
+    /// A hint at how to fix the error, if there is an obvious one.
+    pub fn help(&self) -> Option<String> {
+        match self {
+            ConfigError::UnknownType {
+                suggestion: Some(suggestion),
+                ..
+            }
+            | ConfigError::UnknownVariant {
+                suggestion: Some(suggestion),
+                ..
+            }
+            | ConfigError::UnknownImport {
+                suggestion: Some(suggestion),
+                ..
+            }
+            | ConfigError::UnknownNamespace {
+                suggestion: Some(suggestion),
+                ..
+            } => Some(format!("did you mean '{}'?", suggestion.name)),
+            ConfigError::UnknownNamespace { namespace, .. } => Some(format!(
+                "a file is given a namespace with `use \"{}.cfg\"`",
+                namespace
+            )),
+            ConfigError::AmbiguousName { name, paths, .. } => Some(format!(
+                "pick one by listing it, e.g. `use {} from \"{}\"`",
+                name, paths[0]
+            )),
+            ConfigError::DuplicateImport { .. } => {
+                Some(String::from("give one of them another name with 'as'"))
+            }
+            ConfigError::UnknownVariant {
+                enum_name,
+                variants,
+                ..
+            } if variants.is_empty() => Some(format!("'{}' has no variants", enum_name)),
+            ConfigError::UnknownVariant {
+                enum_name,
+                variants,
+                ..
+            } => Some(format!(
+                "'{}' has the variants {}",
+                enum_name,
+                variants.join(", ")
+            )),
+            ConfigError::UntypedVariant { variant, .. } => {
+                Some(format!("did you mean the string \"{}\"?", variant))
+            }
+            ConfigError::MissingField {
+                field,
+                suggestion: Some(suggestion),
+                ..
+            } => Some(format!(
+                "did you mean '{}' instead of '{}'?",
+                field, suggestion.name
+            )),
+            ConfigError::UnresolvedImport { tried, .. } => {
+                Some(format!("looked for it at {}", tried.join(", ")))
+            }
+            ConfigError::ConflictingDefaults { field, child, .. } => {
+                Some(format!("set '{}' in '{}' to pick its value", field, child))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.path)
+        } else {
+            write!(f, "{}:{}:{}", self.path, self.line, self.column)
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location(), self.message())
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,862 @@
+use crate::error::{ConfigError, Location};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum TokenType {
+    TypeIdentifier,
+    EnumIdentifier,
+    TypeList,
+    StringLiteral,
+    NumberLiteral,
+    BoolLiteral,
+    NullLiteral,
+    Equals,
+    Colon,
+    /// `::` between a namespace and a name in it, as in `items::Item`.
+    PathSeparator,
+    Comma,
+    Plus,
+    QuestionMark,
+    Star,
+    /// `..` or `..=` in a range like `0..=1000`.
+    Range,
+    ParenthesisStart,
+    ParenthesisEnd,
+    CurlyBracketStart,
+    CurlyBracketEnd,
+    SquareBracketStart,
+    SquareBracketEnd,
+    AngleBracketStart,
+    AngleBracketEnd,
+    TypeBool,
+    TypeString,
+    TypeNumber,
+    TypeMap,
+    PossibleIdentifier,
+    Import,
+    From,
+    Eof,
+    Uninitialized,
+}
+
+/// Where a token sits in the source text. `offset` and `length` are in bytes,
+/// `line` and `column` start at 1 and count characters.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct Span {
+    pub offset: usize,
+    pub length: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Token {
+    pub token: String,
+    pub tokentype: TokenType,
+    pub span: Span,
+    /// The `///` comment lines right in front of the token, without the slashes.
+    pub doc: Option<String>,
+}
+
+impl Token {
+    pub fn new(token: &str, tokentype: TokenType) -> Token {
+        Token {
+            token: token.to_string(),
+            tokentype,
+            span: Span::default(),
+            doc: None,
+        }
+    }
+
+    pub fn new_at(token: &str, tokentype: TokenType, span: Span) -> Token {
+        Token {
+            token: token.to_string(),
+            tokentype,
+            span,
+            doc: None,
+        }
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.tokentype {
+            TokenType::TypeIdentifier => write!(f, "TypeIdentifier: {}", self.token),
+            TokenType::EnumIdentifier => write!(f, "EnumIdentifier: {}", self.token),
+            TokenType::PossibleIdentifier => write!(f, "PossibleIdentifier: {}", self.token),
+            TokenType::StringLiteral => write!(f, "StringLiteral: {}", self.token),
+            TokenType::NumberLiteral => write!(f, "NumberLiteral: {}", self.token),
+            TokenType::BoolLiteral => write!(f, "BoolLiteral: {}", self.token),
+            TokenType::NullLiteral => write!(f, "NullLiteral: {}", self.token),
+            TokenType::TypeBool => write!(f, "TypeBool: {}", self.token),
+            TokenType::TypeString => write!(f, "TypeString: {}", self.token),
+            TokenType::TypeNumber => write!(f, "TypeNumber: {}", self.token),
+            TokenType::TypeList => write!(f, "TypeList: {}", self.token),
+            TokenType::TypeMap => write!(f, "TypeMap: {}", self.token),
+            TokenType::CurlyBracketStart => write!(f, "CurlyBracketStart: {}", self.token),
+            TokenType::CurlyBracketEnd => write!(f, "CurlyBracketEnd: {}", self.token),
+            TokenType::SquareBracketStart => write!(f, "SquareBracketStart: {}", self.token),
+            TokenType::SquareBracketEnd => write!(f, "SquareBracketEnd: {}", self.token),
+            TokenType::AngleBracketStart => write!(f, "AngleBracketStart: {}", self.token),
+            TokenType::AngleBracketEnd => write!(f, "AngleBracketEnd: {}", self.token),
+            TokenType::Equals => write!(f, "Equals: {}", self.token),
+            TokenType::Colon => write!(f, "Colon: {}", self.token),
+            TokenType::PathSeparator => write!(f, "PathSeparator: {}", self.token),
+            TokenType::Comma => write!(f, "Comma: {}", self.token),
+            TokenType::Plus => write!(f, "Plus: {}", self.token),
+            TokenType::QuestionMark => write!(f, "QuestionMark: {}", self.token),
+            TokenType::Star => write!(f, "Star: {}", self.token),
+            TokenType::Range => write!(f, "Range: {}", self.token),
+            TokenType::ParenthesisStart => write!(f, "ParenthesisStart: {}", self.token),
+            TokenType::ParenthesisEnd => write!(f, "ParenthesisEnd: {}", self.token),
+            TokenType::Import => write!(f, "Import: {}", self.token),
+            TokenType::From => write!(f, "From: {}", self.token),
+            TokenType::Eof => write!(f, "EOF"),
+            TokenType::Uninitialized => write!(f, "Uninitialized Token"),
+        }
+    }
+}
+
+/// Walks the characters of a file one at a time, keeping track of where it is.
+struct Scanner<'a> {
+    contents: &'a str,
+    path: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+    /// Errors that don't stop a token from being produced, like a malformed number.
+    errors: Vec<ConfigError>,
+    /// `///` lines read since the last token.
+    doc: Option<String>,
+}
+
+#[allow(clippy::result_large_err)]
+impl<'a> Scanner<'a> {
+    fn new(contents: &'a str, path: &'a str) -> Scanner<'a> {
+        Scanner {
+            contents,
+            path,
+            offset: 0,
+            line: 1,
+            column: 1,
+            errors: Vec::new(),
+            doc: None,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.contents[self.offset..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.contents[self.offset..].chars().nth(1)
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let character = self.peek()?;
+        self.offset += character.len_utf8();
+        if character == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(character)
+    }
+
+    fn span_from(&self, start: Span) -> Span {
+        Span {
+            length: self.offset - start.offset,
+            ..start
+        }
+    }
+
+    fn here(&self) -> Span {
+        Span {
+            offset: self.offset,
+            length: 0,
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn error(&self, message: &str, span: Span) -> ConfigError {
+        ConfigError::Lex {
+            message: message.to_string(),
+            location: Location::from_span(self.path, span),
+        }
+    }
+
+    /// Skips whitespace, `//` comments and `/* */` comments, which may be nested.
+    /// The text of `///` comments is kept for the next token.
+    fn skip_trivia(&mut self) {
+        while let Some(character) = self.peek() {
+            if character.is_whitespace() {
+                self.advance();
+            } else if self.contents[self.offset..].starts_with("//") {
+                self.line_comment();
+            } else if self.contents[self.offset..].starts_with("/*") {
+                self.block_comment();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn line_comment(&mut self) {
+        let start = self.offset;
+        while let Some(character) = self.advance() {
+            if character == '\n' {
+                break;
+            }
+        }
+        let comment = self.contents[start..self.offset].trim_end();
+        // `////` is a plain comment, like in Rust.
+        if let Some(text) = comment.strip_prefix("///") {
+            if text.starts_with('/') {
+                return;
+            }
+            let text = text.strip_prefix(' ').unwrap_or(text);
+            match &mut self.doc {
+                Some(doc) => {
+                    doc.push('\n');
+                    doc.push_str(text);
+                }
+                None => self.doc = Some(text.to_string()),
+            }
+        }
+    }
+
+    fn block_comment(&mut self) {
+        let start = self.here();
+        let mut depth = 0;
+        loop {
+            let rest = &self.contents[self.offset..];
+            if rest.starts_with("/*") {
+                self.advance_to(self.offset + 2);
+                depth += 1;
+            } else if rest.starts_with("*/") {
+                self.advance_to(self.offset + 2);
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            } else if self.advance().is_none() {
+                let error = self.error("unterminated block comment", Span { length: 2, ..start });
+                self.errors.push(error);
+                break;
+            }
+        }
+    }
+
+    /// Advances until `offset`, keeping the line and column up to date.
+    fn advance_to(&mut self, offset: usize) {
+        while self.offset < offset && self.advance().is_some() {}
+    }
+
+    /// Consumes a `\` escape sequence and appends the character it stands for.
+    /// A bad escape is reported and left out of the string.
+    fn escape(&mut self, literal: &mut String) {
+        match self.escaped_character() {
+            Ok(Some(character)) => literal.push(character),
+            Ok(None) => (),
+            Err(error) => self.errors.push(error),
+        }
+    }
+
+    fn escaped_character(&mut self) -> Result<Option<char>, ConfigError> {
+        let start = self.here();
+        self.advance();
+        let character = match self.advance() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('u') => self.unicode_escape(start)?,
+            Some(other) => {
+                let message = format!("unknown escape sequence '\\{}'", other);
+                return Err(self.error(&message, self.span_from(start)));
+            }
+            None => return Ok(None),
+        };
+        Ok(Some(character))
+    }
+
+    /// The rest of a `\u{1F600}` escape, after the `u`.
+    fn unicode_escape(&mut self, start: Span) -> Result<char, ConfigError> {
+        if self.peek() != Some('{') {
+            return Err(self.error("expected '{' after '\\u'", self.span_from(start)));
+        }
+        self.advance();
+        let mut digits = String::new();
+        while let Some(character) = self.peek() {
+            if !character.is_ascii_hexdigit() {
+                break;
+            }
+            digits.push(character);
+            self.advance();
+        }
+        if self.peek() != Some('}') {
+            return Err(self.error("unterminated unicode escape", self.span_from(start)));
+        }
+        self.advance();
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .filter(|_| digits.len() <= 6)
+            .and_then(char::from_u32)
+            .ok_or_else(|| {
+                let message = format!("invalid unicode escape '\\u{{{}}}'", digits);
+                self.error(&message, self.span_from(start))
+            })
+    }
+
+    fn string_literal(&mut self, start: Span) -> Result<Token, ConfigError> {
+        let mut literal = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => break,
+                Some('\\') => self.escape(&mut literal),
+                // Line breaks inside of a string are collapsed to a space.
+                Some('\n') => {
+                    self.advance();
+                    literal.push(' ');
+                }
+                Some('\r') => {
+                    self.advance();
+                }
+                Some(character) => {
+                    self.advance();
+                    literal.push(character);
+                }
+                None => {
+                    return Err(self.error("unterminated string literal", self.span_from(start)))
+                }
+            }
+        }
+        self.advance();
+        Ok(Token {
+            token: literal,
+            tokentype: TokenType::StringLiteral,
+            span: self.span_from(start),
+            doc: None,
+        })
+    }
+
+    /// A `"""` string, after the opening quotes. Line breaks are kept, except
+    /// for one right after the opening quotes. When the closing quotes are on a
+    /// line of their own, the indentation in front of them is stripped from
+    /// every line, so the string can be indented along with the file.
+    fn multiline_string_literal(&mut self, start: Span) -> Result<Token, ConfigError> {
+        let rest = &self.contents[self.offset..];
+        let mut characters = rest.char_indices();
+        let mut end = None;
+        while let Some((index, character)) = characters.next() {
+            if character == '\\' {
+                characters.next();
+            } else if rest[index..].starts_with("\"\"\"") {
+                end = Some(index);
+                break;
+            }
+        }
+        let end = match end {
+            Some(end) => end,
+            None => {
+                self.advance_to(self.contents.len());
+                let message = "unterminated multi-line string literal";
+                return Err(self.error(message, self.span_from(start)));
+            }
+        };
+
+        let body = &rest[..end];
+        let (body_end, indent) = match body.rfind('\n') {
+            Some(newline) if body[newline + 1..].chars().all(|c| c == ' ' || c == '\t') => {
+                (newline, body[newline + 1..].to_string())
+            }
+            _ => (end, String::new()),
+        };
+        let body_end = self.offset + body_end;
+        let closing_end = self.offset + end + 3;
+
+        if self.contents[self.offset..].starts_with("\r\n") {
+            self.advance();
+        }
+        if self.peek() == Some('\n') {
+            self.advance();
+        }
+        let mut literal = String::new();
+        let mut at_line_start = true;
+        while self.offset < body_end {
+            if at_line_start {
+                at_line_start = false;
+                for character in indent.chars() {
+                    if self.peek() != Some(character) || self.offset >= body_end {
+                        break;
+                    }
+                    self.advance();
+                }
+                continue;
+            }
+            match self.peek() {
+                Some('\\') => self.escape(&mut literal),
+                Some('\r') => {
+                    self.advance();
+                }
+                Some(character) => {
+                    self.advance();
+                    literal.push(character);
+                    at_line_start = character == '\n';
+                }
+                None => break,
+            }
+        }
+        self.advance_to(closing_end);
+        Ok(Token {
+            token: literal,
+            tokentype: TokenType::StringLiteral,
+            span: self.span_from(start),
+            doc: None,
+        })
+    }
+
+    /// `r"..."` or `r#"..."#`: no escapes, the text is taken exactly as written.
+    /// The number of `#` lets the string contain `"` and `"#`.
+    fn raw_string_literal(&mut self, start: Span) -> Result<Token, ConfigError> {
+        self.advance();
+        let mut hashes = 0;
+        while self.peek() == Some('#') {
+            self.advance();
+            hashes += 1;
+        }
+        self.advance();
+
+        let closing = format!("\"{}", "#".repeat(hashes));
+        let literal = match self.contents[self.offset..].find(&closing) {
+            Some(end) => self.contents[self.offset..self.offset + end].to_string(),
+            None => {
+                self.advance_to(self.contents.len());
+                let message = "unterminated raw string literal";
+                return Err(self.error(message, self.span_from(start)));
+            }
+        };
+        self.advance_to(self.offset + literal.len() + closing.len());
+        Ok(Token {
+            token: literal,
+            tokentype: TokenType::StringLiteral,
+            span: self.span_from(start),
+            doc: None,
+        })
+    }
+
+    fn word(&mut self, start: Span) -> Token {
+        while let Some(character) = self.peek() {
+            if !is_identifier_character(character) {
+                break;
+            }
+            self.advance();
+        }
+        let word = &self.contents[start.offset..self.offset];
+        let tokentype = match word {
+            "true" | "false" => TokenType::BoolLiteral,
+            "null" => TokenType::NullLiteral,
+            "type" => TokenType::TypeIdentifier,
+            "enum" => TokenType::EnumIdentifier,
+            "number" => TokenType::TypeNumber,
+            "bool" => TokenType::TypeBool,
+            "string" => TokenType::TypeString,
+            "map" => TokenType::TypeMap,
+            "list" => TokenType::TypeList,
+            "use" => TokenType::Import,
+            "from" => TokenType::From,
+            _ => TokenType::PossibleIdentifier,
+        };
+        Token::new_at(word, tokentype, self.span_from(start))
+    }
+
+    /// Consumes a group of digits in `radix`, which may be split up by `_`, and
+    /// appends the digits to `literal`. Returns false if the group is empty,
+    /// starts or ends with a `_`, or has two `_` in a row.
+    fn digits(&mut self, radix: u32, literal: &mut String) -> bool {
+        let mut valid = self
+            .peek()
+            .is_some_and(|character| character.is_digit(radix));
+        let mut last = None;
+        while let Some(character) = self.peek() {
+            if character.is_digit(radix) {
+                literal.push(character);
+            } else if character != '_' {
+                break;
+            } else if last == Some('_') {
+                valid = false;
+            }
+            last = self.advance();
+        }
+        if last == Some('_') {
+            valid = false;
+        }
+        valid
+    }
+
+    /// `123`, `-1_000`, `1.5`, `2e-3`, `0xff` or `0b1010`. The token holds the
+    /// number without separators, with hexadecimal and binary integers written
+    /// out in decimal. Numbers with a fraction or an exponent are floats.
+    fn number_literal(&mut self, start: Span) -> Token {
+        let mut literal = String::new();
+        if self.peek() == Some('-') {
+            self.advance();
+            literal.push('-');
+        }
+        let radix = match (self.peek(), self.peek_second()) {
+            (Some('0'), Some('x')) => 16,
+            (Some('0'), Some('b')) => 2,
+            _ => 10,
+        };
+
+        let mut float = false;
+        let mut valid;
+        if radix != 10 {
+            self.advance();
+            self.advance();
+            valid = self.digits(radix, &mut literal);
+        } else {
+            valid = self.digits(10, &mut literal);
+            if self.peek() == Some('.') && self.peek_second().is_some_and(|c| c.is_ascii_digit()) {
+                self.advance();
+                literal.push('.');
+                valid &= self.digits(10, &mut literal);
+                float = true;
+            }
+            if let Some(exponent @ ('e' | 'E')) = self.peek() {
+                self.advance();
+                literal.push(exponent);
+                if let Some(sign @ ('+' | '-')) = self.peek() {
+                    self.advance();
+                    literal.push(sign);
+                }
+                valid &= self.digits(10, &mut literal);
+                float = true;
+            }
+        }
+        // Whatever is stuck to the end, like the `x` in `12x`, belongs to the
+        // literal. A `..` starts a range instead.
+        while let Some(character) = self.peek() {
+            let dot = character == '.' && self.peek_second() != Some('.');
+            if !(is_identifier_character(character) || dot) {
+                break;
+            }
+            self.advance();
+            valid = false;
+        }
+
+        let span = self.span_from(start);
+        let text = &self.contents[start.offset..self.offset];
+        let number = if !valid {
+            Err(format!("invalid number literal '{}'", text))
+        } else if float {
+            match literal.parse::<f64>() {
+                Ok(number) if number.is_finite() => Ok(literal),
+                _ => Err(format!("number literal '{}' is too large", text)),
+            }
+        } else {
+            match i64::from_str_radix(&literal, radix) {
+                Ok(number) => Ok(number.to_string()),
+                Err(_) => Err(format!(
+                    "integer literal '{}' doesn't fit in a 64 bit integer",
+                    text
+                )),
+            }
+        };
+        let token = number.unwrap_or_else(|message| {
+            // Keep the token so the parser doesn't report a missing value as well.
+            self.errors.push(self.error(&message, span));
+            String::from("0")
+        });
+        Token {
+            token,
+            tokentype: TokenType::NumberLiteral,
+            span,
+            doc: None,
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Token, ConfigError> {
+        self.skip_trivia();
+        let start = self.here();
+        let character = match self.peek() {
+            Some(character) => character,
+            None => return Ok(Token::new_at("", TokenType::Eof, start)),
+        };
+
+        if self.contents[self.offset..].starts_with("::") {
+            self.advance_to(self.offset + 2);
+            return Ok(Token::new_at(
+                "::",
+                TokenType::PathSeparator,
+                self.span_from(start),
+            ));
+        }
+        let punctuation = match character {
+            '{' => Some(TokenType::CurlyBracketStart),
+            '}' => Some(TokenType::CurlyBracketEnd),
+            '[' => Some(TokenType::SquareBracketStart),
+            ']' => Some(TokenType::SquareBracketEnd),
+            '<' => Some(TokenType::AngleBracketStart),
+            '>' => Some(TokenType::AngleBracketEnd),
+            '=' => Some(TokenType::Equals),
+            ':' => Some(TokenType::Colon),
+            ',' => Some(TokenType::Comma),
+            '+' => Some(TokenType::Plus),
+            '?' => Some(TokenType::QuestionMark),
+            '*' => Some(TokenType::Star),
+            '(' => Some(TokenType::ParenthesisStart),
+            ')' => Some(TokenType::ParenthesisEnd),
+            _ => None,
+        };
+        if let Some(tokentype) = punctuation {
+            self.advance();
+            return Ok(Token::new_at(
+                &character.to_string(),
+                tokentype,
+                self.span_from(start),
+            ));
+        }
+
+        if self.contents[self.offset..].starts_with("..") {
+            let range = if self.contents[self.offset..].starts_with("..=") {
+                "..="
+            } else {
+                ".."
+            };
+            self.advance_to(self.offset + range.len());
+            return Ok(Token::new_at(
+                range,
+                TokenType::Range,
+                self.span_from(start),
+            ));
+        }
+        if self.contents[self.offset..].starts_with("\"\"\"") {
+            self.advance_to(self.offset + 3);
+            return self.multiline_string_literal(start);
+        }
+        if character == '"' {
+            self.advance();
+            return self.string_literal(start);
+        }
+        if character == 'r'
+            && self.contents[self.offset + 1..]
+                .trim_start_matches('#')
+                .starts_with('"')
+        {
+            return self.raw_string_literal(start);
+        }
+        if character.is_ascii_digit()
+            || (character == '-' && self.peek_second().is_some_and(|c| c.is_ascii_digit()))
+        {
+            return Ok(self.number_literal(start));
+        }
+        if is_identifier_character(character) {
+            return Ok(self.word(start));
+        }
+
+        self.advance();
+        Err(self.error(
+            &format!("unexpected character '{}'", character),
+            self.span_from(start),
+        ))
+    }
+}
+
+pub(crate) fn is_identifier_character(character: char) -> bool {
+    character.is_alphanumeric() || character == '_'
+}
+
+/// Turns the text of a file into the list of symbols the parser walks over,
+/// terminated by an `Eof` token. Characters that can't start a token are
+/// reported and skipped, so the rest of the file still gets tokenized.
+pub fn tokenize(contents: &str, path: &str) -> (Vec<Token>, Vec<ConfigError>) {
+    let mut scanner = Scanner::new(contents, path);
+    let mut symbols = vec![];
+    let mut errors = vec![];
+    loop {
+        let next_token = scanner.next_token();
+        errors.append(&mut scanner.errors);
+        match next_token {
+            Ok(mut symbol) => {
+                symbol.doc = scanner.doc.take();
+                let is_eof = symbol.tokentype == TokenType::Eof;
+                symbols.push(symbol);
+                if is_eof {
+                    break;
+                }
+            }
+            Err(error) => errors.push(error),
+        }
+    }
+    (symbols, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tokenize, TokenType};
+
+    /// The type and text of every token but the final `Eof`.
+    fn tokens(source: &str) -> Vec<(TokenType, String)> {
+        let (symbols, errors) = tokenize(source, "test.cfg");
+        assert!(errors.is_empty(), "unexpected errors: {:?}", errors);
+        symbols
+            .into_iter()
+            .filter(|symbol| symbol.tokentype != TokenType::Eof)
+            .map(|symbol| (symbol.tokentype, symbol.token))
+            .collect()
+    }
+
+    fn errors(source: &str) -> Vec<String> {
+        let (_, errors) = tokenize(source, "test.cfg");
+        errors.iter().map(|error| error.message()).collect()
+    }
+
+    fn string(source: &str) -> String {
+        match &tokens(source)[..] {
+            [(TokenType::StringLiteral, literal)] => literal.clone(),
+            other => panic!("expected a single string, found {:?}", other),
+        }
+    }
+
+    fn number(source: &str) -> String {
+        match &tokens(source)[..] {
+            [(TokenType::NumberLiteral, literal)] => literal.clone(),
+            other => panic!("expected a single number, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn tokens_carry_their_line_and_column() {
+        let (symbols, _) = tokenize("type Goblin {\n  life: number = 10\n}", "test.cfg");
+        let life = &symbols[3];
+        assert_eq!(life.token, "life");
+        assert_eq!(
+            (life.span.line, life.span.column, life.span.length),
+            (2, 3, 4)
+        );
+        assert_eq!(symbols.last().unwrap().tokentype, TokenType::Eof);
+    }
+
+    #[test]
+    fn keywords_and_punctuation() {
+        let types: Vec<TokenType> = tokens("use * from enum type x::y { } [ ] = : , ..= .. ? null")
+            .into_iter()
+            .map(|(tokentype, _)| tokentype)
+            .collect();
+        assert_eq!(
+            types,
+            [
+                TokenType::Import,
+                TokenType::Star,
+                TokenType::From,
+                TokenType::EnumIdentifier,
+                TokenType::TypeIdentifier,
+                TokenType::PossibleIdentifier,
+                TokenType::PathSeparator,
+                TokenType::PossibleIdentifier,
+                TokenType::CurlyBracketStart,
+                TokenType::CurlyBracketEnd,
+                TokenType::SquareBracketStart,
+                TokenType::SquareBracketEnd,
+                TokenType::Equals,
+                TokenType::Colon,
+                TokenType::Comma,
+                TokenType::Range,
+                TokenType::Range,
+                TokenType::QuestionMark,
+                TokenType::NullLiteral,
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_are_skipped_and_doc_comments_kept() {
+        let (symbols, comment_errors) = tokenize(
+            "// note\n/* outer /* nested */ still a comment */\n/// First.\n/// Second.\nGoblin",
+            "test.cfg",
+        );
+        assert!(comment_errors.is_empty());
+        assert_eq!(symbols[0].token, "Goblin");
+        assert_eq!(symbols[0].doc.as_deref(), Some("First.\nSecond."));
+        assert_eq!(
+            errors("/* open /* nested */"),
+            ["unterminated block comment"]
+        );
+    }
+
+    #[test]
+    fn unknown_characters_are_reported_and_skipped() {
+        let (symbols, errors) = tokenize("a = 1 $ b = 2", "test.cfg");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location().column, 7);
+        assert!(symbols.iter().any(|symbol| symbol.token == "b"));
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(number("1_000"), "1000");
+        assert_eq!(number("-42"), "-42");
+        assert_eq!(number("0xff"), "255");
+        assert_eq!(number("0b1010"), "10");
+        assert_eq!(number("1.5"), "1.5");
+        assert_eq!(number("2e-3"), "2e-3");
+        assert_eq!(number("-9223372036854775808"), "-9223372036854775808");
+    }
+
+    #[test]
+    fn malformed_numbers() {
+        assert_eq!(errors("1."), ["invalid number literal '1.'"]);
+        assert_eq!(errors("0x"), ["invalid number literal '0x'"]);
+        assert_eq!(errors("1_"), ["invalid number literal '1_'"]);
+        assert_eq!(errors("1__0"), ["invalid number literal '1__0'"]);
+        assert_eq!(errors("12x"), ["invalid number literal '12x'"]);
+        assert_eq!(errors("1e"), ["invalid number literal '1e'"]);
+        assert_eq!(
+            errors("9223372036854775808"),
+            ["integer literal '9223372036854775808' doesn't fit in a 64 bit integer"]
+        );
+        assert_eq!(errors("1e999"), ["number literal '1e999' is too large"]);
+    }
+
+    #[test]
+    fn range_after_an_integer() {
+        assert_eq!(
+            tokens("0..=10"),
+            [
+                (TokenType::NumberLiteral, String::from("0")),
+                (TokenType::Range, String::from("..=")),
+                (TokenType::NumberLiteral, String::from("10")),
+            ]
+        );
+        assert_eq!(tokens("1..")[1], (TokenType::Range, String::from("..")));
+    }
+
+    #[test]
+    fn string_escapes() {
+        assert_eq!(string(r#""a\"b\\c\nd\te""#), "a\"b\\c\nd\te");
+        assert_eq!(string(r#""\u{1F600}""#), "\u{1F600}");
+        assert_eq!(string("\"two\nlines\""), "two lines");
+        assert_eq!(string(r##"r#"no \n "escapes""#"##), r#"no \n "escapes""#);
+        assert_eq!(errors(r#""\q""#), ["unknown escape sequence '\\q'"]);
+        assert_eq!(
+            errors(r#""\u{110000}""#),
+            ["invalid unicode escape '\\u{110000}'"]
+        );
+        assert_eq!(errors(r#""\u41""#), ["expected '{' after '\\u'"]);
+        assert_eq!(errors("\"open"), ["unterminated string literal"]);
+    }
+
+    #[test]
+    fn multi_line_strings_strip_the_closing_indentation() {
+        let source = "\"\"\"\n    first\n      indented\n    last\n    \"\"\"";
+        assert_eq!(string(source), "first\n  indented\nlast");
+        assert_eq!(string("\"\"\"kept\n  as is\"\"\""), "kept\n  as is");
+        assert_eq!(string("\"\"\"\n  a \\\"\"\" b\n  \"\"\""), "a \"\"\" b");
+        assert_eq!(
+            errors("\"\"\"open"),
+            ["unterminated multi-line string literal"]
+        );
+    }
+}
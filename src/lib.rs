@@ -0,0 +1,114 @@
+//! Compiler for the config file format: parses `.cfg` files, applies user
+//! types to objects and emits the result as JSON.
+//!
+//! ```no_run
+//! let parsed = config_file::parse_file("monsters.cfg").unwrap();
+//! let document = config_file::resolve(&parsed).unwrap();
+//! println!("{}", config_file::to_json(&document));
+//! ```
+
+mod ast;
+mod diagnostic;
+mod emit;
+mod error;
+mod lexer;
+mod loader;
+mod parser;
+mod resolve;
+mod typecheck;
+
+use std::io::Read;
+
+pub use ast::{
+    Bounds, Constraint, Document, Field, Import, ImportedName, Object, ParsedFile, Type, Value,
+    Variant,
+};
+pub use diagnostic::{render, Sources};
+pub use emit::JsonOptions;
+pub use error::{ConfigError, Location, Note, Suggestion};
+pub use lexer::{Token, TokenType};
+pub use resolve::ResolveOptions;
+
+/// Parses the text of a config file.
+#[allow(clippy::result_large_err)]
+pub fn parse_str(contents: &str) -> Result<ParsedFile, ConfigError> {
+    parse_str_with_path(contents, "<string>")
+}
+
+/// Parses the text of a config file, reporting errors against `path`.
+#[allow(clippy::result_large_err)]
+pub fn parse_str_with_path(contents: &str, path: &str) -> Result<ParsedFile, ConfigError> {
+    first_error(parse_partial(contents, path))
+}
+
+/// Reads and parses a config file.
+#[allow(clippy::result_large_err)]
+pub fn parse_file<P: AsRef<std::path::Path>>(path: P) -> Result<ParsedFile, ConfigError> {
+    first_error(parse_file_partial(path))
+}
+
+/// Parses as much of a config file as possible. Declarations with errors in
+/// them are skipped, and every error found along the way is returned next to
+/// what could be parsed.
+pub fn parse_partial(contents: &str, path: &str) -> (ParsedFile, Vec<ConfigError>) {
+    let (symbols, mut errors) = lexer::tokenize(contents, path);
+    let (file, parse_errors) = parser::construct_ast(&symbols, path);
+    errors.extend(parse_errors);
+    errors.sort_by_key(|error| (error.location().line, error.location().column));
+    (file, errors)
+}
+
+/// Reads a config file and parses as much of it as possible, like [`parse_partial`].
+pub fn parse_file_partial<P: AsRef<std::path::Path>>(path: P) -> (ParsedFile, Vec<ConfigError>) {
+    let path_name = path.as_ref().display().to_string();
+    let mut contents = String::new();
+    let read = std::fs::File::open(&path).and_then(|mut file| file.read_to_string(&mut contents));
+    if let Err(error) = read {
+        let error = ConfigError::Io {
+            path: path_name.clone(),
+            error,
+            location: Location::new(&path_name, 0, 0),
+        };
+        let file = ParsedFile {
+            path: path_name,
+            ..ParsedFile::new()
+        };
+        return (file, vec![error]);
+    }
+    parse_partial(&contents, &path_name)
+}
+
+#[allow(clippy::result_large_err)]
+fn first_error((file, errors): (ParsedFile, Vec<ConfigError>)) -> Result<ParsedFile, ConfigError> {
+    match errors.into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(file),
+    }
+}
+
+/// Applies each object's type to it, filling in the defaults of every field
+/// the object doesn't set itself, then checks every field against its type.
+/// Imported files are looked for next to the file importing them, then in the
+/// directories listed in the `CONFIG_FILE_PATH` environment variable.
+#[allow(clippy::result_large_err)]
+pub fn resolve(file: &ParsedFile) -> Result<Document, ConfigError> {
+    resolve_with(file, &ResolveOptions::default())
+}
+
+/// Resolves a parsed file like [`resolve`], with the given options.
+#[allow(clippy::result_large_err)]
+pub fn resolve_with(file: &ParsedFile, options: &ResolveOptions) -> Result<Document, ConfigError> {
+    let document = resolve::fill_object_fields(file, options)?;
+    typecheck::check_document(&document.fields, &document.objects)?;
+    Ok(document)
+}
+
+/// Serializes a resolved document to a (not prettified) JSON string.
+pub fn to_json(document: &Document) -> String {
+    to_json_with(document, &JsonOptions::default())
+}
+
+/// Serializes a resolved document like [`to_json`], with the given options.
+pub fn to_json_with(document: &Document, options: &JsonOptions) -> String {
+    emit::emit_json(&document.objects, &document.fields, options)
+}
@@ -0,0 +1,645 @@
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::ast::{Import, ImportedName, ParsedFile};
+use crate::diagnostic::closest_match;
+use crate::error::{ConfigError, Location, Suggestion};
+use crate::resolve::ResolveOptions;
+
+/// A file taking part in resolving another: the file itself, or one it
+/// imports from, directly or through other files.
+struct Module {
+    /// The path the file was read from, the one its locations point into.
+    path: String,
+    /// The canonical path, so a file reached through paths written
+    /// differently is still only read once.
+    key: PathBuf,
+    file: Rc<ParsedFile>,
+    /// The key of the file each of its `use` directives reads from, in order.
+    dependencies: Vec<PathBuf>,
+}
+
+/// What a name a file imports refers to: the key of the file it comes from and
+/// the name there, and where the importing file names it.
+struct Binding {
+    local_name: String,
+    target: (PathBuf, String),
+    location: Location,
+}
+
+/// The files reachable through `use` directives from the file being resolved,
+/// each parsed exactly once, and which of them imports which.
+pub(crate) struct ModuleGraph {
+    /// The file being resolved comes first.
+    modules: Vec<Module>,
+    /// Where imported files not found next to the importing file are looked for, in order.
+    include_dirs: Vec<PathBuf>,
+}
+
+#[allow(clippy::result_large_err)]
+impl ModuleGraph {
+    /// Reads every file `root` imports from, and every file those import from,
+    /// checking that each name a `use` directive lists is there and exported,
+    /// and that no two directives of a file bring in different things under the
+    /// same name. A file that ends up importing itself is an error.
+    pub(crate) fn load(
+        root: &ParsedFile,
+        options: &ResolveOptions,
+    ) -> Result<ModuleGraph, ConfigError> {
+        let mut include_dirs = options.include_dirs.clone();
+        if let Some(search_path) = std::env::var_os("CONFIG_FILE_PATH") {
+            include_dirs.extend(std::env::split_paths(&search_path));
+        }
+        let mut graph = ModuleGraph {
+            modules: Vec::new(),
+            include_dirs,
+        };
+        graph.add(Rc::new(root.clone()), &mut Vec::new())?;
+        Ok(graph)
+    }
+
+    /// Adds `file` and everything it imports. `loading` holds the keys of the
+    /// files whose imports are being read, the chain of imports that led to `file`.
+    fn add(&mut self, file: Rc<ParsedFile>, loading: &mut Vec<PathBuf>) -> Result<(), ConfigError> {
+        let index = self.modules.len();
+        let key = module_key(&file.path);
+        self.modules.push(Module {
+            path: file.path.clone(),
+            key: key.clone(),
+            file: Rc::clone(&file),
+            dependencies: Vec::new(),
+        });
+
+        loading.push(key);
+        let mut names: Vec<Binding> = vec![];
+        let mut namespaces: Vec<Binding> = vec![];
+        for import in &file.imports {
+            let path = self.locate(import)?;
+            let dependency = module_key(&path);
+            if let Some(start) = loading.iter().position(|loaded| *loaded == dependency) {
+                let mut cycle: Vec<String> = loading[start..]
+                    .iter()
+                    .map(|loaded| self.module(loaded).path.clone())
+                    .collect();
+                cycle.push(cycle[0].clone());
+                return Err(ConfigError::ImportCycle {
+                    cycle,
+                    location: import.location.clone(),
+                });
+            }
+            if !self.modules.iter().any(|module| module.key == dependency) {
+                self.add(Rc::new(read(&path, import)?), loading)?;
+            }
+
+            let imported = &self.module(&dependency).file;
+            for name in &import.names {
+                check_imported(name, import, imported)?;
+                bind(
+                    &mut names,
+                    Binding {
+                        local_name: name.local_name.clone(),
+                        target: (dependency.clone(), name.name.clone()),
+                        location: name.location.clone(),
+                    },
+                )?;
+            }
+            if let Some(namespace) = &import.namespace {
+                bind(
+                    &mut namespaces,
+                    Binding {
+                        local_name: namespace.clone(),
+                        target: (dependency.clone(), String::new()),
+                        location: import.location.clone(),
+                    },
+                )?;
+            }
+            self.modules[index].dependencies.push(dependency);
+        }
+        loading.pop();
+        Ok(())
+    }
+
+    fn module(&self, key: &Path) -> &Module {
+        self.modules
+            .iter()
+            .find(|module| module.key == key)
+            .unwrap_or(&self.modules[0])
+    }
+
+    /// The file read from `path`. Anything else is in the file being resolved.
+    pub(crate) fn file_at(&self, path: &str) -> Rc<ParsedFile> {
+        let module = self
+            .modules
+            .iter()
+            .find(|module| module.path == path)
+            .unwrap_or(&self.modules[0]);
+        Rc::clone(&module.file)
+    }
+
+    /// The file a `use` directive of one of the modules reads from.
+    pub(crate) fn imported_file(&self, import: &Import) -> Option<Rc<ParsedFile>> {
+        let importing = self
+            .modules
+            .iter()
+            .find(|module| module.path == import.location.path)?;
+        let position = importing
+            .file
+            .imports
+            .iter()
+            .position(|directive| directive == import)?;
+        let key = importing.dependencies.get(position)?;
+        Some(Rc::clone(&self.module(key).file))
+    }
+
+    /// The path of the file a `use` directive names. A relative path is looked
+    /// up next to the file the directive is in, then in each include directory.
+    fn locate(&self, import: &Import) -> Result<String, ConfigError> {
+        let written = Path::new(&import.path);
+        let importing_directory = Path::new(&import.location.path)
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        let mut candidates = vec![importing_directory.join(written)];
+        if written.is_relative() {
+            for candidate in self.include_dirs.iter().map(|dir| dir.join(written)) {
+                if !candidates.contains(&candidate) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+        match candidates.iter().find(|candidate| candidate.is_file()) {
+            Some(found) => Ok(found.display().to_string()),
+            None => Err(ConfigError::UnresolvedImport {
+                path: import.path.clone(),
+                tried: candidates
+                    .iter()
+                    .map(|candidate| candidate.display().to_string())
+                    .collect(),
+                location: import.location.clone(),
+            }),
+        }
+    }
+}
+
+/// What tells files apart: the canonical path, or the path as given for a
+/// file that isn't on disk, like one parsed from a string.
+fn module_key(path: &str) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
+/// Parses the file at `path`. A file that can't be read is reported at the `use` directive.
+#[allow(clippy::result_large_err)]
+fn read(path: &str, import: &Import) -> Result<ParsedFile, ConfigError> {
+    match crate::parse_file(path) {
+        Err(ConfigError::Io { path, error, .. }) => Err(ConfigError::Io {
+            path,
+            error,
+            location: import.location.clone(),
+        }),
+        parsed => parsed,
+    }
+}
+
+/// Adds `binding` to the names a file imports, unless the name is already taken
+/// by something else.
+#[allow(clippy::result_large_err)]
+fn bind(bound: &mut Vec<Binding>, binding: Binding) -> Result<(), ConfigError> {
+    let first = bound
+        .iter()
+        .find(|first| first.local_name == binding.local_name);
+    match first {
+        Some(first) if first.target != binding.target => Err(ConfigError::DuplicateImport {
+            name: binding.local_name,
+            location: binding.location,
+            first_location: first.location.clone(),
+        }),
+        Some(_) => Ok(()),
+        None => {
+            bound.push(binding);
+            Ok(())
+        }
+    }
+}
+
+/// Checks that `file`, which `import` reads from, has `name` and lets other files import it.
+#[allow(clippy::result_large_err)]
+pub(crate) fn check_imported(
+    name: &ImportedName,
+    import: &Import,
+    file: &ParsedFile,
+) -> Result<(), ConfigError> {
+    let declared_at = file.declared_at(&name.name).or_else(|| {
+        file.imported_name(&name.name)
+            .map(|(_, imported)| &imported.location)
+    });
+    match declared_at {
+        None => Err(unknown_import(name, import, file)),
+        Some(declared_at) if !file.exports_name(&name.name) => Err(ConfigError::PrivateImport {
+            name: name.name.clone(),
+            path: import.path.clone(),
+            location: name.location.clone(),
+            declared_at: declared_at.clone(),
+        }),
+        Some(_) => Ok(()),
+    }
+}
+
+/// The error for a name `import` lists that `file` doesn't have, suggesting the
+/// closest one it exports.
+fn unknown_import(name: &ImportedName, import: &Import, file: &ParsedFile) -> ConfigError {
+    let types = file
+        .user_types
+        .iter()
+        .map(|usertype| (&usertype.typename, &usertype.location));
+    let objects = file
+        .user_objects
+        .iter()
+        .map(|object| (&object.object_name, &object.location));
+    let fields = file
+        .user_fields
+        .iter()
+        .map(|field| (&field.identifier, &field.location));
+    let declared: Vec<(&String, &Location)> = types
+        .chain(objects)
+        .chain(fields)
+        .filter(|(name, _)| file.exports_name(name))
+        .collect();
+    let suggestion = closest_match(&name.name, declared.iter().map(|(name, _)| name.as_str()))
+        .and_then(|closest| declared.iter().find(|(name, _)| *name == closest))
+        .map(|(name, location)| Suggestion {
+            name: name.to_string(),
+            location: (*location).clone(),
+        });
+    ConfigError::UnknownImport {
+        name: name.name.clone(),
+        path: import.path.clone(),
+        location: name.location.clone(),
+        suggestion,
+    }
+}
+
+/// The error for a qualified name whose namespace `file` doesn't give any file,
+/// suggesting the closest one it does.
+pub(crate) fn unknown_namespace(
+    namespace: &str,
+    file: &ParsedFile,
+    location: &Location,
+) -> ConfigError {
+    let namespaces: Vec<(&String, &Location)> = file
+        .imports
+        .iter()
+        .filter_map(|import| Some((import.namespace.as_ref()?, &import.location)))
+        .collect();
+    let suggestion = closest_match(namespace, namespaces.iter().map(|(name, _)| name.as_str()))
+        .and_then(|closest| namespaces.iter().find(|(name, _)| *name == closest))
+        .map(|(name, location)| Suggestion {
+            name: name.to_string(),
+            location: (*location).clone(),
+        });
+    ConfigError::UnknownNamespace {
+        namespace: namespace.to_string(),
+        location: location.clone(),
+        suggestion,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::rc::Rc;
+
+    use super::ModuleGraph;
+    use crate::{ConfigError, Document, ResolveOptions};
+
+    /// Writes `files` into a directory of their own for the test called `test`.
+    fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("config_file_{}", test));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (path, contents) in files {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn resolve_in(
+        dir: &Path,
+        file: &str,
+        options: &ResolveOptions,
+    ) -> Result<Document, ConfigError> {
+        let parsed = crate::parse_file(dir.join(file))?;
+        crate::resolve_with(&parsed, options)
+    }
+
+    fn json_in(dir: &Path, file: &str) -> String {
+        crate::to_json(&resolve_in(dir, file, &ResolveOptions::new()).unwrap())
+    }
+
+    fn error_in(dir: &Path, file: &str) -> ConfigError {
+        resolve_in(dir, file, &ResolveOptions::new()).unwrap_err()
+    }
+
+    fn path_in(dir: &Path, file: &str) -> String {
+        dir.join(file).display().to_string()
+    }
+
+    #[test]
+    fn use_lists_names_and_gives_them_aliases() {
+        let dir = write_files(
+            "use_lists",
+            &[
+                (
+                    "lib.cfg",
+                    "type Monster { life: number = 1 }\ntype Door { open = false }\nenum Element { Fire, Water }\n",
+                ),
+                ("more.cfg", "type Item { weight: number = 2 }\n"),
+                (
+                    "main.cfg",
+                    "use { Monster, Door } from \"lib.cfg\"\nuse Element as Elem from \"lib.cfg\"\n\
+                     use * from \"more.cfg\"\ntype Spell { element: Elem = Fire }\n\
+                     g : Monster { }\nd : Door { }\ns : Spell { }\ni : Item { }\n",
+                ),
+            ],
+        );
+        assert_eq!(
+            json_in(&dir, "main.cfg"),
+            r#"{"g":{"life":1},"d":{"open":false},"s":{"element":"Fire"},"i":{"weight":2}}"#
+        );
+    }
+
+    #[test]
+    fn unknown_import_suggests_a_declared_name() {
+        let dir = write_files(
+            "unknown_import",
+            &[
+                ("lib.cfg", "type Monster { }\ntype Door { }\n"),
+                ("main.cfg", "use { Monster, Dor } from \"lib.cfg\"\n"),
+            ],
+        );
+        match error_in(&dir, "main.cfg") {
+            ConfigError::UnknownImport {
+                name,
+                path,
+                suggestion: Some(suggestion),
+                ..
+            } => {
+                assert_eq!((name.as_str(), path.as_str()), ("Dor", "lib.cfg"));
+                assert_eq!(suggestion.name, "Door");
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    fn imports_are_found_next_to_the_importing_file() {
+        let dir = write_files(
+            "relative_imports",
+            &[
+                (
+                    "main.cfg",
+                    "use Item from \"lib/items.cfg\"\nx : Item { }\n",
+                ),
+                (
+                    "lib/items.cfg",
+                    "use Base from \"base.cfg\"\ntype Item : Base { }\n",
+                ),
+                ("lib/base.cfg", "type Base { weight: number = 1 }\n"),
+            ],
+        );
+        assert_eq!(json_in(&dir, "main.cfg"), r#"{"x":{"weight":1}}"#);
+    }
+
+    #[test]
+    fn include_dirs_are_searched_before_the_search_path() {
+        let dir = write_files(
+            "include_dirs",
+            &[("main.cfg", "use Item from \"shared.cfg\"\nx : Item { }\n")],
+        );
+        let mut options = ResolveOptions::new();
+        options.include_dirs = vec![dir.join("a"), dir.join("b")];
+        std::env::set_var("CONFIG_FILE_PATH", dir.join("c"));
+
+        match resolve_in(&dir, "main.cfg", &options).unwrap_err() {
+            ConfigError::UnresolvedImport { tried, .. } => assert_eq!(
+                tried,
+                [
+                    path_in(&dir, "shared.cfg"),
+                    path_in(&dir, "a/shared.cfg"),
+                    path_in(&dir, "b/shared.cfg"),
+                    path_in(&dir, "c/shared.cfg"),
+                ]
+            ),
+            error => panic!("unexpected error: {}", error),
+        }
+
+        for (library, weight) in [("c", 3), ("b", 2)] {
+            let library = dir.join(library);
+            std::fs::create_dir_all(&library).unwrap();
+            std::fs::write(
+                library.join("shared.cfg"),
+                format!("type Item {{ weight: number = {} }}\n", weight),
+            )
+            .unwrap();
+            let document = resolve_in(&dir, "main.cfg", &options).unwrap();
+            assert_eq!(
+                crate::to_json(&document),
+                format!(r#"{{"x":{{"weight":{}}}}}"#, weight)
+            );
+        }
+        std::env::remove_var("CONFIG_FILE_PATH");
+    }
+
+    #[test]
+    fn import_cycles_are_reported_with_their_path() {
+        let dir = write_files(
+            "import_cycle",
+            &[
+                ("a.cfg", "use B from \"b.cfg\"\ntype A { }\n"),
+                ("b.cfg", "use C from \"c.cfg\"\ntype B { }\n"),
+                ("c.cfg", "use A from \"a.cfg\"\ntype C { }\n"),
+            ],
+        );
+        match error_in(&dir, "a.cfg") {
+            ConfigError::ImportCycle { cycle, location } => {
+                assert_eq!(
+                    cycle,
+                    [
+                        path_in(&dir, "a.cfg"),
+                        path_in(&dir, "b.cfg"),
+                        path_in(&dir, "c.cfg"),
+                        path_in(&dir, "a.cfg"),
+                    ]
+                );
+                assert_eq!(location.path, path_in(&dir, "c.cfg"));
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    fn file_imported_twice_is_parsed_once() {
+        let dir = write_files(
+            "diamond_import",
+            &[
+                (
+                    "main.cfg",
+                    "use Left from \"left.cfg\"\nuse Right from \"right.cfg\"\nx : Left + Right { }\n",
+                ),
+                ("left.cfg", "use Base from \"common.cfg\"\ntype Left : Base { }\n"),
+                ("right.cfg", "use Base from \"common.cfg\"\ntype Right : Base { }\n"),
+                ("common.cfg", "type Base { weight: number = 1 }\n"),
+            ],
+        );
+        let root = crate::parse_file(dir.join("main.cfg")).unwrap();
+        let graph = ModuleGraph::load(&root, &ResolveOptions::new()).unwrap();
+        assert_eq!(graph.modules.len(), 4);
+        let left = graph.imported_file(&root.imports[0]).unwrap();
+        let right = graph.imported_file(&root.imports[1]).unwrap();
+        assert!(Rc::ptr_eq(
+            &graph.imported_file(&left.imports[0]).unwrap(),
+            &graph.imported_file(&right.imports[0]).unwrap()
+        ));
+        assert_eq!(json_in(&dir, "main.cfg"), r#"{"x":{"weight":1}}"#);
+    }
+
+    #[test]
+    fn name_two_wildcards_bring_in_is_ambiguous() {
+        let dir = write_files(
+            "ambiguous_name",
+            &[
+                ("items.cfg", "type Item { a = 1 }\n"),
+                ("weapons.cfg", "type Item { b = 2 }\n"),
+                (
+                    "main.cfg",
+                    "use * from \"items.cfg\"\nuse * from \"weapons.cfg\"\nx : Item { }\n",
+                ),
+                (
+                    "listed.cfg",
+                    "use * from \"items.cfg\"\nuse * from \"weapons.cfg\"\nuse Item from \"weapons.cfg\"\nx : Item { }\n",
+                ),
+            ],
+        );
+        match error_in(&dir, "main.cfg") {
+            ConfigError::AmbiguousName { name, paths, .. } => {
+                assert_eq!(name, "Item");
+                assert_eq!(paths, ["items.cfg", "weapons.cfg"]);
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+        assert_eq!(json_in(&dir, "listed.cfg"), r#"{"x":{"b":2}}"#);
+    }
+
+    #[test]
+    fn name_listed_from_two_files_is_a_duplicate() {
+        let dir = write_files(
+            "duplicate_import",
+            &[
+                ("items.cfg", "type Item { a = 1 }\n"),
+                ("weapons.cfg", "type Item { b = 2 }\n"),
+                (
+                    "main.cfg",
+                    "use Item from \"items.cfg\"\nuse Item from \"weapons.cfg\"\n",
+                ),
+                (
+                    "aliased.cfg",
+                    "use Item from \"items.cfg\"\nuse Item as Weapon from \"weapons.cfg\"\n\
+                     use Item from \"items.cfg\"\nx : Weapon { }\n",
+                ),
+            ],
+        );
+        match error_in(&dir, "main.cfg") {
+            ConfigError::DuplicateImport {
+                name,
+                location,
+                first_location,
+            } => {
+                assert_eq!(name, "Item");
+                assert_eq!((first_location.line, location.line), (1, 2));
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+        assert_eq!(json_in(&dir, "aliased.cfg"), r#"{"x":{"b":2}}"#);
+    }
+
+    #[test]
+    fn names_without_export_are_private() {
+        let dir = write_files(
+            "private_import",
+            &[
+                (
+                    "lib.cfg",
+                    "export type Item { weight: number = 1 }\ntype Helper { x: number = 1 }\n",
+                ),
+                ("main.cfg", "use Helper from \"lib.cfg\"\n"),
+                ("wildcard.cfg", "use * from \"lib.cfg\"\nx : Helper { }\n"),
+            ],
+        );
+        match error_in(&dir, "main.cfg") {
+            ConfigError::PrivateImport {
+                name, declared_at, ..
+            } => {
+                assert_eq!(name, "Helper");
+                assert_eq!(declared_at.line, 2);
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+        match error_in(&dir, "wildcard.cfg") {
+            ConfigError::UnknownType { typename, .. } => assert_eq!(typename, "Helper"),
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    fn export_use_passes_an_imported_name_on() {
+        let dir = write_files(
+            "export_use",
+            &[
+                ("items.cfg", "type Item { weight: number = 1 }\n"),
+                (
+                    "lib.cfg",
+                    "export use Item from \"items.cfg\"\nuse Item as Other from \"items.cfg\"\nexport type Bag { }\n",
+                ),
+                (
+                    "main.cfg",
+                    "use { Item, Bag } from \"lib.cfg\"\nx : Item { }\n",
+                ),
+                ("other.cfg", "use Other from \"lib.cfg\"\n"),
+            ],
+        );
+        assert_eq!(json_in(&dir, "main.cfg"), r#"{"x":{"weight":1}}"#);
+        match error_in(&dir, "other.cfg") {
+            ConfigError::PrivateImport { name, .. } => assert_eq!(name, "Other"),
+            error => panic!("unexpected error: {}", error),
+        }
+
+        let errors = crate::parse_partial("export use * from \"items.cfg\"\n", "test.cfg").1;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message(),
+            "expected a `use` directive listing the names to export, found 'use *'"
+        );
+    }
+
+    #[test]
+    fn unknown_namespace_suggests_a_close_one() {
+        let dir = write_files(
+            "unknown_namespace",
+            &[
+                ("items.cfg", "type Item { }\n"),
+                ("main.cfg", "use \"items.cfg\"\nx : itemz::Item { }\n"),
+            ],
+        );
+        match error_in(&dir, "main.cfg") {
+            ConfigError::UnknownNamespace {
+                namespace,
+                suggestion: Some(suggestion),
+                ..
+            } => {
+                assert_eq!(namespace, "itemz");
+                assert_eq!(suggestion.name, "items");
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+}
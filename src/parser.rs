@@ -0,0 +1,980 @@
+use crate::ast::{
+    Bounds, Constraint, Field, Import, ImportedName, Object, ParsedFile, Type, Value, Variant,
+};
+use crate::diagnostic::closest_match;
+use crate::error::{ConfigError, Location, Suggestion};
+use crate::lexer::{is_identifier_character, Token, TokenType};
+use crate::loader::unknown_namespace;
+use crate::typecheck::check_value;
+
+pub(crate) struct TokenTraverse {
+    /// The parents set by the last `[Label]`, for objects declared without any.
+    current_parents: Vec<Type>,
+    /// Inside a `type` block a name after a colon is always a type. Elsewhere
+    /// `name: Fire` sets a variant, and only `name: Type = value` names a type.
+    in_type_declaration: bool,
+    token_index: usize,
+    current_token: Token,
+    file: ParsedFile,
+    errors: Vec<ConfigError>,
+    path: String,
+}
+
+/// How a token type is named in "expected ..." errors.
+fn describe_token_type(tokentype: TokenType) -> &'static str {
+    match tokentype {
+        TokenType::PossibleIdentifier => "an identifier",
+        TokenType::CurlyBracketStart => "'{'",
+        TokenType::CurlyBracketEnd => "'}'",
+        TokenType::SquareBracketEnd => "']'",
+        TokenType::AngleBracketStart => "'<'",
+        TokenType::AngleBracketEnd => "'>'",
+        TokenType::Equals => "'='",
+        TokenType::Colon => "':'",
+        TokenType::PathSeparator => "'::'",
+        TokenType::Comma => "','",
+        TokenType::Plus => "'+'",
+        TokenType::Range => "'..' or '..='",
+        TokenType::ParenthesisStart => "'('",
+        TokenType::ParenthesisEnd => "')'",
+        TokenType::StringLiteral => "a string",
+        TokenType::From => "'from'",
+        _ => "a value",
+    }
+}
+
+#[allow(clippy::result_large_err)]
+impl TokenTraverse {
+    pub fn new(tokens: &[Token], path: &str) -> TokenTraverse {
+        TokenTraverse {
+            token_index: 0,
+            current_parents: Vec::new(),
+            in_type_declaration: false,
+            current_token: tokens[0].clone(),
+            file: ParsedFile {
+                path: path.to_string(),
+                ..ParsedFile::new()
+            },
+            errors: Vec::new(),
+            path: path.to_string(),
+        }
+    }
+
+    pub fn token_is_type(token: &Token) -> bool {
+        token.tokentype == TokenType::TypeBool
+            || token.tokentype == TokenType::TypeString
+            || token.tokentype == TokenType::TypeNumber
+            || token.tokentype == TokenType::TypeList
+            || token.tokentype == TokenType::TypeMap
+    }
+
+    fn location(&self) -> Location {
+        Location::from_span(&self.path, self.current_token.span)
+    }
+
+    fn syntax_error(&self, expected: &str) -> ConfigError {
+        let found = match self.current_token.tokentype {
+            TokenType::Eof => String::from("end of file"),
+            TokenType::StringLiteral => format!("\"{}\"", self.current_token.token),
+            _ => format!("'{}'", self.current_token.token),
+        };
+        ConfigError::Syntax {
+            expected: expected.to_string(),
+            found,
+            location: self.location(),
+        }
+    }
+
+    fn accept(&mut self, tokentype: TokenType, tokens: &[Token]) -> bool {
+        if self.current_token.tokentype == tokentype {
+            self.next_token(tokens);
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, tokentype: TokenType, tokens: &[Token]) -> Result<(), ConfigError> {
+        if self.accept(tokentype, tokens) {
+            return Ok(());
+        }
+        Err(self.syntax_error(describe_token_type(tokentype)))
+    }
+
+    /// Consumes an identifier and returns its name.
+    fn identifier(&mut self, tokens: &[Token]) -> Result<String, ConfigError> {
+        let identifier = self.current_token.token.clone();
+        self.expect(TokenType::PossibleIdentifier, tokens)?;
+        Ok(identifier)
+    }
+
+    /// Consumes a name, or a name in a namespace like `items::Item`, and returns
+    /// it with the location it covers.
+    fn qualified_name(&mut self, tokens: &[Token]) -> Result<(String, Location), ConfigError> {
+        let mut location = self.location();
+        let start = self.current_token.span;
+        let mut name = self.identifier(tokens)?;
+        if self.accept(TokenType::PathSeparator, tokens) {
+            let end = self.current_token.span;
+            name = format!("{}::{}", name, self.identifier(tokens)?);
+            location.length = end.offset + end.length - start.offset;
+        }
+        Ok((name, location))
+    }
+
+    /// Consumes the current token if it's the identifier `keyword`, one of the
+    /// words like `as` that are only keywords in certain places.
+    fn accept_keyword(&mut self, keyword: &str, tokens: &[Token]) -> bool {
+        if self.current_token.tokentype == TokenType::PossibleIdentifier
+            && self.current_token.token == keyword
+        {
+            self.next_token(tokens);
+            return true;
+        }
+        false
+    }
+
+    /// A literal, a `[value, value, ...]` list or a `{ key = value, ... }` map.
+    fn value(&mut self, tokens: &[Token]) -> Result<Value, ConfigError> {
+        if self.current_token.tokentype == TokenType::CurlyBracketStart {
+            let entries = self.fields(tokens)?;
+            for entry in &entries {
+                if !entry.initialized {
+                    self.errors.push(ConfigError::Syntax {
+                        expected: String::from("a value"),
+                        found: format!("'{}' without one", entry.identifier),
+                        location: entry.location.clone(),
+                    });
+                }
+            }
+            return Ok(Value::Map(entries));
+        }
+        if self.accept(TokenType::SquareBracketStart, tokens) {
+            let mut elements = vec![];
+            while !self.accept(TokenType::SquareBracketEnd, tokens) {
+                elements.push(self.value(tokens)?);
+                if !self.accept(TokenType::Comma, tokens)
+                    && self.current_token.tokentype != TokenType::SquareBracketEnd
+                {
+                    return Err(self.syntax_error("',' or ']'"));
+                }
+            }
+            return Ok(Value::List(elements));
+        }
+
+        let value = match self.current_token.tokentype {
+            TokenType::StringLiteral => Value::String(self.current_token.token.clone()),
+            TokenType::BoolLiteral => Value::Bool(self.current_token.token == "true"),
+            TokenType::NullLiteral => Value::Null,
+            TokenType::NumberLiteral => self.number()?,
+            TokenType::PossibleIdentifier => {
+                // A name starting the next field, e.g. `b` in `a =` followed by `b = 2`
+                // on the next line, means the value is missing rather than being `b`.
+                let starts_field = matches!(
+                    self.token_after_name(tokens).tokentype,
+                    TokenType::Equals | TokenType::Colon | TokenType::CurlyBracketStart
+                );
+                if starts_field && self.starts_line(tokens) {
+                    let previous = &tokens[self.token_index.saturating_sub(1)];
+                    return Err(ConfigError::Syntax {
+                        expected: String::from("a value"),
+                        found: String::from("the end of the line"),
+                        location: Location::from_span(&self.path, previous.span),
+                    });
+                }
+                let (name, _) = self.qualified_name(tokens)?;
+                return Ok(Value::Variant { name, index: None });
+            }
+            _ => return Err(self.syntax_error("a value")),
+        };
+        self.next_token(tokens);
+        Ok(value)
+    }
+
+    /// The value of the current number literal. Numbers without a fraction are integers.
+    fn number(&self) -> Result<Value, ConfigError> {
+        let literal = &self.current_token.token;
+        if let Ok(integer) = literal.parse::<i64>() {
+            return Ok(Value::Integer(integer));
+        }
+        match literal.parse::<f64>() {
+            Ok(float) if float.is_finite() => Ok(Value::Float(float)),
+            _ => Err(ConfigError::Lex {
+                message: format!("invalid number literal '{}'", literal),
+                location: self.location(),
+            }),
+        }
+    }
+
+    /// `string`, `number`, `bool`, `list<T>`, `map<K, V>` or the name of a user defined type
+    /// (which may be declared further down, so it's looked up when objects are resolved).
+    /// Builtin types may be followed by `(constraint, ...)`, and any type by `?`.
+    fn type_name(&mut self, tokens: &[Token]) -> Result<Type, ConfigError> {
+        let location = self.location();
+        let mut field_type = match self.current_token.tokentype {
+            TokenType::TypeBool => Type::new_bool_type(),
+            TokenType::TypeString => Type::new_string_type(),
+            TokenType::TypeNumber => Type::new_number_type(),
+            TokenType::TypeList => {
+                self.next_token(tokens);
+                self.expect(TokenType::AngleBracketStart, tokens)?;
+                let element_type = self.type_name(tokens)?;
+                if self.current_token.tokentype != TokenType::AngleBracketEnd {
+                    return Err(self.syntax_error("'>'"));
+                }
+                Type::new_list_type(element_type)
+            }
+            TokenType::TypeMap => {
+                self.next_token(tokens);
+                self.expect(TokenType::AngleBracketStart, tokens)?;
+                let key_location = self.location();
+                let key_type = self.type_name(tokens)?;
+                if key_type != Type::new_string_type() {
+                    // Keys end up as JSON object keys, so they can only be strings.
+                    self.errors.push(ConfigError::Syntax {
+                        expected: String::from("'string' as the key type"),
+                        found: format!("'{}'", key_type),
+                        location: key_location,
+                    });
+                }
+                self.expect(TokenType::Comma, tokens)?;
+                let value_type = self.type_name(tokens)?;
+                if self.current_token.tokentype != TokenType::AngleBracketEnd {
+                    return Err(self.syntax_error("'>'"));
+                }
+                Type::new_map_type(key_type, value_type)
+            }
+            TokenType::PossibleIdentifier => {
+                let (typename, location) = self.qualified_name(tokens)?;
+                return Ok(Type {
+                    typename,
+                    location,
+                    optional: self.accept(TokenType::QuestionMark, tokens),
+                    ..Type::new()
+                });
+            }
+            _ => return Err(self.syntax_error("a type")),
+        };
+        self.next_token(tokens);
+        if field_type.is_builtin() && self.current_token.tokentype == TokenType::ParenthesisStart {
+            field_type.constraints = self.constraints(tokens, &field_type)?;
+            field_type.location = location;
+        }
+        field_type.optional = self.accept(TokenType::QuestionMark, tokens);
+        Ok(field_type)
+    }
+
+    /// `(constraint, constraint, ...)` after a builtin type. Numbers take a range,
+    /// strings `len` and `match`, lists and maps `len`.
+    fn constraints(
+        &mut self,
+        tokens: &[Token],
+        field_type: &Type,
+    ) -> Result<Vec<Constraint>, ConfigError> {
+        self.expect(TokenType::ParenthesisStart, tokens)?;
+        let mut constraints = vec![];
+        loop {
+            let location = self.location();
+            let constraint = match self.current_token.token.as_str() {
+                "len" if self.current_token.tokentype == TokenType::PossibleIdentifier => {
+                    self.next_token(tokens);
+                    Constraint::Length(self.bounds(tokens)?)
+                }
+                "match" if self.current_token.tokentype == TokenType::PossibleIdentifier => {
+                    self.next_token(tokens);
+                    if self.current_token.tokentype != TokenType::StringLiteral {
+                        return Err(self.syntax_error("a regular expression"));
+                    }
+                    let pattern = self.current_token.token.clone();
+                    if regex::Regex::new(&pattern).is_err() {
+                        self.errors.push(ConfigError::Lex {
+                            message: format!("invalid regular expression {:?}", pattern),
+                            location: self.location(),
+                        });
+                    }
+                    self.next_token(tokens);
+                    Constraint::Pattern(pattern)
+                }
+                _ => Constraint::Range(self.bounds(tokens)?),
+            };
+
+            let applies = matches!(
+                (field_type.typename.as_str(), &constraint),
+                ("number", Constraint::Range(_))
+                    | ("string", Constraint::Length(_))
+                    | ("string", Constraint::Pattern(_))
+                    | ("list", Constraint::Length(_))
+                    | ("map", Constraint::Length(_))
+            );
+            if applies {
+                constraints.push(constraint);
+            } else {
+                self.errors.push(ConfigError::Syntax {
+                    expected: format!("a constraint on '{}'", field_type.typename),
+                    found: format!("'{}'", constraint),
+                    location,
+                });
+            }
+            if !self.accept(TokenType::Comma, tokens) {
+                break;
+            }
+        }
+        self.expect(TokenType::ParenthesisEnd, tokens)?;
+        Ok(constraints)
+    }
+
+    /// `min..max` or `min..=max`, where either number may be left out.
+    fn bounds(&mut self, tokens: &[Token]) -> Result<Bounds, ConfigError> {
+        let mut bounds = Bounds::default();
+        if self.current_token.tokentype == TokenType::NumberLiteral {
+            bounds.min = Some(self.bound(tokens)?);
+        }
+        if self.current_token.tokentype != TokenType::Range {
+            return Err(self.syntax_error(describe_token_type(TokenType::Range)));
+        }
+        bounds.inclusive = self.current_token.token == "..=";
+        self.next_token(tokens);
+        if bounds.inclusive || self.current_token.tokentype == TokenType::NumberLiteral {
+            if self.current_token.tokentype != TokenType::NumberLiteral {
+                return Err(self.syntax_error("the end of the range"));
+            }
+            bounds.max = Some(self.bound(tokens)?);
+        }
+        Ok(bounds)
+    }
+
+    /// Consumes the number literal at one end of a range.
+    fn bound(&mut self, tokens: &[Token]) -> Result<f64, ConfigError> {
+        let bound = self.number()?.as_number().unwrap_or_default();
+        self.next_token(tokens);
+        Ok(bound)
+    }
+
+    /// `name = value`, `name: value`, `name: type`, `name: type = value`
+    /// or a nested `name { ... }` object.
+    fn field(&mut self, tokens: &[Token]) -> Result<Field, ConfigError> {
+        let mut field = Field::new();
+        field.location = self.location();
+        field.doc = self.current_token.doc.clone();
+        field.identifier = self.identifier(tokens)?;
+
+        if self.current_token.tokentype == TokenType::CurlyBracketStart {
+            let mut object = Object::new();
+            object.object_name = field.identifier.clone();
+            object.location = field.location.clone();
+            object.doc = field.doc.clone();
+            field.value_location = self.location();
+            object.fields = self.fields(tokens)?;
+            field.value = Value::Object(Box::new(object));
+            field.initialized = true;
+            return Ok(field);
+        }
+
+        if self.accept(TokenType::Colon, tokens) {
+            let names_type = self.current_token.tokentype == TokenType::PossibleIdentifier
+                && (self.in_type_declaration
+                    || matches!(
+                        self.token_after_name(tokens).tokentype,
+                        TokenType::Equals | TokenType::QuestionMark
+                    ));
+            if TokenTraverse::token_is_type(&self.current_token) || names_type {
+                field.identifier_type = self.type_name(tokens)?;
+                if !self.accept(TokenType::Equals, tokens) {
+                    return Ok(field);
+                }
+            }
+        } else {
+            self.expect(TokenType::Equals, tokens)?;
+        }
+
+        field.value_location = self.location();
+        field.value = self.value(tokens)?;
+        field.initialized = true;
+
+        if let Err(error) = check_value(
+            &field.value,
+            &field.identifier_type,
+            &field,
+            &field.value_location,
+        ) {
+            self.errors.push(error);
+        }
+        Ok(field)
+    }
+
+    /// `{ field, field, ... }`. Commas between fields are optional.
+    /// A broken field is reported and skipped, keeping the rest of the block.
+    fn fields(&mut self, tokens: &[Token]) -> Result<Vec<Field>, ConfigError> {
+        let mut fields = vec![];
+        self.expect(TokenType::CurlyBracketStart, tokens)?;
+        while !self.accept(TokenType::CurlyBracketEnd, tokens) {
+            if self.current_token.tokentype == TokenType::Eof {
+                return Err(self.syntax_error("'}'"));
+            }
+            if self.at_declaration_keyword() {
+                // Most likely a missing '}', keep what was parsed so far.
+                self.errors.push(self.syntax_error("'}'"));
+                break;
+            }
+            match self.field(tokens) {
+                Ok(field) => fields.push(field),
+                Err(error) => {
+                    self.errors.push(error);
+                    if !self.skip_to_next_field(tokens) {
+                        break;
+                    }
+                }
+            }
+            self.accept(TokenType::Comma, tokens);
+        }
+        Ok(fields)
+    }
+
+    /// `use Name from "file.cfg"`, `use { Name, Name } from "file.cfg"`,
+    /// `use * from "file.cfg"` or `use "file.cfg"`. Each name can be given an
+    /// alias with `as`, and so can the namespace.
+    fn import(&mut self, tokens: &[Token]) -> Result<(), ConfigError> {
+        self.expect(TokenType::Import, tokens)?;
+        let location = self.location();
+        if self.current_token.tokentype == TokenType::StringLiteral {
+            return self.namespace_import(tokens);
+        }
+        let mut names = vec![];
+        let wildcard = self.accept(TokenType::Star, tokens);
+        if wildcard {
+            // Which names there are is only known once the file is read.
+        } else if self.accept(TokenType::CurlyBracketStart, tokens) {
+            while !self.accept(TokenType::CurlyBracketEnd, tokens) {
+                names.push(self.imported_name(tokens)?);
+                if !self.accept(TokenType::Comma, tokens)
+                    && self.current_token.tokentype != TokenType::CurlyBracketEnd
+                {
+                    return Err(self.syntax_error("',' or '}'"));
+                }
+            }
+        } else {
+            names.push(self.imported_name(tokens)?);
+        }
+        self.expect(TokenType::From, tokens)?;
+        if self.current_token.tokentype != TokenType::StringLiteral {
+            return Err(self.syntax_error("a file path"));
+        }
+        let path = self.current_token.token.clone();
+        self.next_token(tokens);
+        self.file.imports.push(Import {
+            names,
+            wildcard,
+            namespace: None,
+            path,
+            location,
+        });
+        Ok(())
+    }
+
+    /// The `"file.cfg"` or `"file.cfg" as name` of a `use` directive that
+    /// makes a file a namespace. Without `as` the namespace is named after the file.
+    fn namespace_import(&mut self, tokens: &[Token]) -> Result<(), ConfigError> {
+        let location = self.location();
+        let path = self.current_token.token.clone();
+        self.next_token(tokens);
+        let namespace = if self.accept_keyword("as", tokens) {
+            self.identifier(tokens)?
+        } else {
+            let stem = std::path::Path::new(&path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let is_name = stem.chars().all(is_identifier_character)
+                && stem
+                    .chars()
+                    .next()
+                    .is_some_and(|first| !first.is_ascii_digit());
+            if !is_name {
+                return Err(ConfigError::Syntax {
+                    expected: String::from("'as' and a namespace for a file whose name isn't one"),
+                    found: format!("\"{}\"", path),
+                    location,
+                });
+            }
+            stem
+        };
+        self.file.imports.push(Import {
+            names: vec![],
+            wildcard: false,
+            namespace: Some(namespace),
+            path,
+            location,
+        });
+        Ok(())
+    }
+
+    /// `Name` or `Name as Alias`.
+    fn imported_name(&mut self, tokens: &[Token]) -> Result<ImportedName, ConfigError> {
+        let location = self.location();
+        let name = self.identifier(tokens)?;
+        let mut local_name = name.clone();
+        if self.accept_keyword("as", tokens) {
+            local_name = self.identifier(tokens)?;
+        }
+        Ok(ImportedName {
+            name,
+            local_name,
+            location,
+        })
+    }
+
+    /// `type Name { ... }`
+    fn type_declaration(&mut self, tokens: &[Token]) -> Result<(), ConfigError> {
+        let mut usertype = Type::new();
+        usertype.doc = self.current_token.doc.clone();
+        self.expect(TokenType::TypeIdentifier, tokens)?;
+        usertype.location = self.location();
+        usertype.typename = self.identifier(tokens)?;
+        if self.accept(TokenType::Colon, tokens) {
+            // Like field types, parents may be declared further down.
+            usertype.parents = self.parents(tokens)?;
+        }
+        self.in_type_declaration = true;
+        let fields = self.fields(tokens);
+        self.in_type_declaration = false;
+        usertype.fields = fields?;
+        self.file.user_types.push(usertype);
+        Ok(())
+    }
+
+    /// `enum Name { Variant, Variant, ... }`. Commas between variants are optional.
+    fn enum_declaration(&mut self, tokens: &[Token]) -> Result<(), ConfigError> {
+        let mut enumtype = Type::new();
+        enumtype.doc = self.current_token.doc.clone();
+        self.expect(TokenType::EnumIdentifier, tokens)?;
+        enumtype.location = self.location();
+        enumtype.typename = self.identifier(tokens)?;
+        self.expect(TokenType::CurlyBracketStart, tokens)?;
+        let mut variants: Vec<Variant> = vec![];
+        while !self.accept(TokenType::CurlyBracketEnd, tokens) {
+            let variant = Variant {
+                location: self.location(),
+                doc: self.current_token.doc.clone(),
+                name: self.identifier(tokens)?,
+            };
+            if variants
+                .iter()
+                .any(|declared| declared.name == variant.name)
+            {
+                self.errors.push(ConfigError::Syntax {
+                    expected: String::from("a variant that isn't declared yet"),
+                    found: format!("'{}' again", variant.name),
+                    location: variant.location,
+                });
+            } else {
+                variants.push(variant);
+            }
+            self.accept(TokenType::Comma, tokens);
+        }
+        enumtype.variants = Some(variants);
+        self.file.user_types.push(enumtype);
+        Ok(())
+    }
+
+    /// `Parent + Parent + ...`, the names after the colon of a type or object.
+    fn parents(&mut self, tokens: &[Token]) -> Result<Vec<Type>, ConfigError> {
+        let mut parents = vec![];
+        loop {
+            let (typename, location) = self.qualified_name(tokens)?;
+            parents.push(Type {
+                typename,
+                location,
+                ..Type::new()
+            });
+            if !self.accept(TokenType::Plus, tokens) {
+                return Ok(parents);
+            }
+        }
+    }
+
+    /// Reports the parents of an object that aren't a type or an object declared
+    /// before it, and leaves them out.
+    fn known_parents(&mut self, parents: Vec<Type>) -> Vec<Type> {
+        let mut known = vec![];
+        for parent in parents {
+            if let Some((namespace, _)) = parent.typename.split_once("::") {
+                // What the namespace has is only known once its file is read.
+                if self.file.namespace(namespace).is_some() {
+                    known.push(parent);
+                } else {
+                    self.errors
+                        .push(unknown_namespace(namespace, &self.file, &parent.location));
+                }
+                continue;
+            }
+            let is_object = self
+                .file
+                .user_objects
+                .iter()
+                .any(|object| object.object_name == parent.typename);
+            // Names brought in by `use *` aren't known until the file is read.
+            let imported = self.file.imported_name(&parent.typename).is_some()
+                || self.file.imports.iter().any(|import| import.wildcard);
+            if is_object || imported || self.find_type(&parent.typename).is_some() {
+                known.push(parent);
+            } else {
+                self.errors
+                    .push(self.unknown_type(parent.typename, parent.location));
+            }
+        }
+        known
+    }
+
+    /// `[Parent + ...]` sets the parents of every following object without any.
+    /// A doc comment above the label documents the declaration after it.
+    fn label(&mut self, tokens: &[Token]) -> Result<(), ConfigError> {
+        let doc = self.current_token.doc.clone();
+        self.expect(TokenType::SquareBracketStart, tokens)?;
+        let parents = self.parents(tokens)?;
+        self.expect(TokenType::SquareBracketEnd, tokens)?;
+        if let Some(doc) = doc {
+            self.current_token.doc = Some(match self.current_token.doc.take() {
+                Some(own) => format!("{}\n{}", doc, own),
+                None => doc,
+            });
+        }
+        self.current_parents = self.known_parents(parents);
+        Ok(())
+    }
+
+    /// Any top level identifier: either `name = value` or an object.
+    fn top_level_identifier(&mut self, tokens: &[Token]) -> Result<(), ConfigError> {
+        if self.look_at_next_token(tokens).tokentype == TokenType::Equals {
+            let mut userfield = Field::new();
+            userfield.location = self.location();
+            userfield.doc = self.current_token.doc.clone();
+            userfield.identifier = self.identifier(tokens)?;
+            self.expect(TokenType::Equals, tokens)?;
+            userfield.value_location = self.location();
+            userfield.value = self.value(tokens)?;
+            userfield.initialized = true;
+            self.file.user_fields.push(userfield);
+            return Ok(());
+        }
+
+        // Otherwise, this is an object
+        let mut userobject = Object::new();
+        userobject.location = self.location();
+        userobject.doc = self.current_token.doc.clone();
+        userobject.object_name = self.identifier(tokens)?;
+
+        if self.accept(TokenType::Colon, tokens) {
+            // Unknown parents are left out, keeping the object so its fields still get checked.
+            let parents = self.parents(tokens)?;
+            userobject.parents = self.known_parents(parents);
+        } else {
+            userobject.parents = self.current_parents.clone();
+        }
+
+        userobject.fields = self.fields(tokens)?;
+        self.file.user_objects.push(userobject);
+        Ok(())
+    }
+
+    /// Whether the current token is `export` in front of a declaration, rather
+    /// than a field or an object called `export`.
+    fn at_export(&self, tokens: &[Token]) -> bool {
+        self.current_token.token == "export"
+            && matches!(
+                self.look_at_next_token(tokens).tokentype,
+                TokenType::Import
+                    | TokenType::TypeIdentifier
+                    | TokenType::EnumIdentifier
+                    | TokenType::PossibleIdentifier
+            )
+    }
+
+    /// `export` followed by a declaration, or by a `use` directive listing names,
+    /// lets other files import what it declares or lists.
+    fn export(&mut self, tokens: &[Token]) -> Result<(), ConfigError> {
+        let doc = self.current_token.doc.clone();
+        self.next_token(tokens);
+        if self.current_token.doc.is_none() {
+            self.current_token.doc = doc;
+        }
+        let types = self.file.user_types.len();
+        let objects = self.file.user_objects.len();
+        let fields = self.file.user_fields.len();
+        let imports = self.file.imports.len();
+        match self.current_token.tokentype {
+            TokenType::Import => self.import(tokens)?,
+            TokenType::TypeIdentifier => self.type_declaration(tokens)?,
+            TokenType::EnumIdentifier => self.enum_declaration(tokens)?,
+            _ => self.top_level_identifier(tokens)?,
+        }
+
+        let file = &mut self.file;
+        let mut exported: Vec<String> = file.user_types[types..]
+            .iter()
+            .map(|usertype| usertype.typename.clone())
+            .collect();
+        exported.extend(
+            file.user_objects[objects..]
+                .iter()
+                .map(|object| object.object_name.clone()),
+        );
+        exported.extend(
+            file.user_fields[fields..]
+                .iter()
+                .map(|field| field.identifier.clone()),
+        );
+        for import in &file.imports[imports..] {
+            if import.wildcard || import.namespace.is_some() {
+                self.errors.push(ConfigError::Syntax {
+                    expected: String::from("a `use` directive listing the names to export"),
+                    found: if import.wildcard {
+                        String::from("'use *'")
+                    } else {
+                        format!("'use \"{}\"'", import.path)
+                    },
+                    location: import.location.clone(),
+                });
+            }
+            exported.extend(import.names.iter().map(|name| name.local_name.clone()));
+        }
+        file.exports.extend(exported);
+        Ok(())
+    }
+
+    fn block(&mut self, tokens: &[Token]) {
+        loop {
+            let start = self.token_index;
+            let declaration = match self.current_token.tokentype {
+                TokenType::Import => self.import(tokens),
+                TokenType::TypeIdentifier => self.type_declaration(tokens),
+                TokenType::EnumIdentifier => self.enum_declaration(tokens),
+                TokenType::SquareBracketStart => self.label(tokens),
+                TokenType::PossibleIdentifier if self.at_export(tokens) => self.export(tokens),
+                TokenType::PossibleIdentifier => self.top_level_identifier(tokens),
+                TokenType::Eof => break,
+                _ => Err(self.syntax_error("a declaration")),
+            };
+            if let Err(error) = declaration {
+                self.errors.push(error);
+                self.skip_to_next_declaration(tokens);
+                if self.token_index == start {
+                    self.next_token(tokens);
+                }
+            }
+        }
+    }
+
+    /// Whether the current token is the first one on its line.
+    fn starts_line(&self, tokens: &[Token]) -> bool {
+        self.token_index == 0
+            || tokens[self.token_index - 1].span.line < self.current_token.span.line
+    }
+
+    /// Whether the current token is a keyword that can only start a top level declaration.
+    fn at_declaration_keyword(&self) -> bool {
+        matches!(
+            self.current_token.tokentype,
+            TokenType::Import | TokenType::TypeIdentifier | TokenType::EnumIdentifier
+        )
+    }
+
+    /// Whether the current token looks like the beginning of a top level declaration.
+    /// Keywords always do; names and labels only at the start of an unindented line,
+    /// which is only a guess good enough to recover from an error with.
+    fn at_declaration_start(&self, tokens: &[Token]) -> bool {
+        if self.at_declaration_keyword() {
+            return true;
+        }
+        match self.current_token.tokentype {
+            TokenType::PossibleIdentifier | TokenType::SquareBracketStart => {
+                self.current_token.span.column == 1 && self.starts_line(tokens)
+            }
+            _ => false,
+        }
+    }
+
+    /// Skips the rest of a broken field. Returns `false` if the enclosing block
+    /// ended, or seems to have, before another field started.
+    fn skip_to_next_field(&mut self, tokens: &[Token]) -> bool {
+        let mut depth = 0;
+        loop {
+            if depth == 0 && self.at_declaration_start(tokens) {
+                return false;
+            }
+            match self.current_token.tokentype {
+                TokenType::Eof => return false,
+                TokenType::Comma if depth == 0 => return true,
+                TokenType::PossibleIdentifier if depth == 0 && self.starts_line(tokens) => {
+                    return true
+                }
+                TokenType::CurlyBracketStart => depth += 1,
+                TokenType::CurlyBracketEnd => {
+                    if depth == 0 {
+                        self.next_token(tokens);
+                        return false;
+                    }
+                    depth -= 1;
+                }
+                _ => (),
+            }
+            self.next_token(tokens);
+        }
+    }
+
+    /// Skips the rest of a broken declaration: up to and including the `}` that
+    /// closes it, or up to whatever looks like the next declaration.
+    fn skip_to_next_declaration(&mut self, tokens: &[Token]) {
+        let mut depth = 0;
+        loop {
+            if depth == 0 && self.at_declaration_start(tokens) {
+                return;
+            }
+            match self.current_token.tokentype {
+                TokenType::Eof => return,
+                TokenType::CurlyBracketStart => depth += 1,
+                TokenType::CurlyBracketEnd => {
+                    depth -= 1;
+                    if depth <= 0 {
+                        self.next_token(tokens);
+                        return;
+                    }
+                }
+                _ => (),
+            }
+            self.next_token(tokens);
+        }
+    }
+
+    /// The error for a parent that isn't declared, suggesting the closest type
+    /// or object declared so far.
+    fn unknown_type(&self, typename: String, location: Location) -> ConfigError {
+        let declared_objects = self
+            .file
+            .user_objects
+            .iter()
+            .map(|object| (object.object_name.as_str(), &object.location));
+        let declared_types = self
+            .file
+            .user_types
+            .iter()
+            .map(|usertype| (usertype.typename.as_str(), &usertype.location));
+        let imported_names = self
+            .file
+            .imports
+            .iter()
+            .flat_map(|import| import.names.iter())
+            .map(|imported| (imported.local_name.as_str(), &imported.location));
+        let declared: Vec<(&str, &Location)> = declared_types
+            .chain(declared_objects)
+            .chain(imported_names)
+            .collect();
+        let suggestion = closest_match(&typename, declared.iter().map(|(name, _)| *name))
+            .and_then(|name| declared.iter().find(|(declared, _)| *declared == name))
+            .map(|(name, location)| Suggestion {
+                name: name.to_string(),
+                location: (*location).clone(),
+            });
+        ConfigError::UnknownType {
+            typename,
+            location,
+            suggestion,
+        }
+    }
+
+    fn find_type(&self, typename: &str) -> Option<Type> {
+        self.file
+            .user_types
+            .iter()
+            .find(|v| v.typename == typename)
+            .cloned()
+    }
+
+    /// The token after the name the current token starts, which may be qualified.
+    fn token_after_name(&self, tokens: &[Token]) -> Token {
+        let mut index = self.token_index + 1;
+        if tokens
+            .get(index)
+            .is_some_and(|token| token.tokentype == TokenType::PathSeparator)
+        {
+            index += 2;
+        }
+        tokens[index.min(tokens.len() - 1)].clone()
+    }
+
+    fn look_at_next_token(&self, tokens: &[Token]) -> Token {
+        tokens[(self.token_index + 1).min(tokens.len() - 1)].clone()
+    }
+
+    fn next_token(&mut self, tokens: &[Token]) {
+        // Stay on the EOF token instead of running past the end.
+        if self.token_index + 1 < tokens.len() {
+            self.token_index += 1;
+        }
+        self.current_token = tokens[self.token_index].clone();
+    }
+}
+
+/// Parses every declaration it can. Broken declarations are left out of the
+/// returned file and reported in the list of errors instead.
+pub(crate) fn construct_ast(tokens: &[Token], path: &str) -> (ParsedFile, Vec<ConfigError>) {
+    let mut token_traversal = TokenTraverse::new(tokens, path);
+    token_traversal.block(tokens);
+    (token_traversal.file, token_traversal.errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ConfigError;
+
+    fn errors(source: &str) -> Vec<ConfigError> {
+        crate::parse_partial(source, "test.cfg").1
+    }
+
+    #[test]
+    fn missing_value_before_next_field_is_reported_at_its_line() {
+        let errors = errors("a =\nb = 2\n");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message(),
+            "expected a value, found the end of the line"
+        );
+        assert_eq!(
+            (errors[0].location().line, errors[0].location().column),
+            (1, 3)
+        );
+
+        let (file, errors) = crate::parse_partial("O {\n  x =\n  y = 1\n}\n", "test.cfg");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location().line, 2);
+        assert_eq!(file.user_objects[0].fields[0].identifier, "y");
+    }
+
+    #[test]
+    fn name_on_the_next_line_is_still_a_value() {
+        assert!(
+            errors("enum E { Fire }\ntype T { e: E }\nO : T {\n  e =\n    Fire\n}\n").is_empty()
+        );
+    }
+
+    #[test]
+    fn doc_comment_above_a_label_documents_the_next_object() {
+        let file = crate::parse_str(
+            "type Monster { }\n/// Monsters of the first level.\n[Monster]\n/// The weakest.\nGoblin { }\n",
+        )
+        .unwrap();
+        assert_eq!(
+            file.user_objects[0].doc.as_deref(),
+            Some("Monsters of the first level.\nThe weakest.")
+        );
+    }
+
+    #[test]
+    fn fields_may_start_at_the_beginning_of_the_line() {
+        let (file, errors) = crate::parse_partial(
+            "type Monster {\nlife: number = 10\nname: string\n}\nGoblin : Monster {\nname = \"g\"\n}\n",
+            "test.cfg",
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(file.user_types[0].fields.len(), 2);
+        assert_eq!(file.user_objects[0].fields[0].identifier, "name");
+    }
+}
@@ -0,0 +1,1162 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::ast::{Document, Field, Import, ImportedName, Object, ParsedFile, Type, Value, Variant};
+use crate::diagnostic::closest_match;
+use crate::error::{ConfigError, Location, Suggestion};
+use crate::loader::{check_imported, unknown_namespace, ModuleGraph};
+use crate::typecheck::{check_constraints, check_declared, check_value};
+
+/// Looks up the types and prototype objects objects and fields refer to,
+/// in the file being resolved or the files it imports, and fills in the defaults they declare.
+struct TypeResolver {
+    modules: ModuleGraph,
+    /// Types whose fields are being filled in right now. A field of one of these
+    /// types can't default to that type's defaults, or recursive types would never end.
+    filling: Vec<String>,
+    /// The top level fields whose values are having names in them replaced, by
+    /// file path and name, to catch fields that refer to each other in a circle.
+    linking: Vec<(String, String)>,
+    /// Objects already resolved, by the path of the file declaring them and their
+    /// position among its objects. Objects inheriting from the same one share it.
+    objects: HashMap<(String, usize), Rc<Object>>,
+    /// Types already resolved, by the path of the file declaring them and their name.
+    types: HashMap<(String, String), Type>,
+}
+
+/// Choices about how a parsed file is resolved.
+#[derive(Clone, Debug, Default)]
+pub struct ResolveOptions {
+    /// Directories searched, in order, for imported files that aren't next to
+    /// the file importing them. The directories in the `CONFIG_FILE_PATH`
+    /// environment variable are searched after these.
+    pub include_dirs: Vec<PathBuf>,
+}
+
+impl ResolveOptions {
+    pub fn new() -> ResolveOptions {
+        ResolveOptions {
+            include_dirs: Vec::new(),
+        }
+    }
+}
+
+/// A name brought in by a `use` directive: the file it comes from, the name it
+/// has there and where the directive lists it.
+struct Imported {
+    file: Rc<ParsedFile>,
+    name: String,
+    location: Location,
+}
+
+#[allow(clippy::result_large_err)]
+impl TypeResolver {
+    fn new(modules: ModuleGraph) -> TypeResolver {
+        TypeResolver {
+            modules,
+            filling: Vec::new(),
+            linking: Vec::new(),
+            objects: HashMap::new(),
+            types: HashMap::new(),
+        }
+    }
+
+    /// Returns the declaration of the type `requested` names, with the fields
+    /// of its parents merged in. `requested` may be nothing more than a name
+    /// and the location it was written at, which tells which file to look in.
+    fn find_type(&mut self, requested: &Type) -> Result<Type, ConfigError> {
+        let scope = self.file_at(&requested.location.path);
+        self.resolve_type(requested, &scope, &mut Vec::new())
+    }
+
+    /// The file read from `path`. Anything not read for a `use` directive is in the main file.
+    fn file_at(&self, path: &str) -> Rc<ParsedFile> {
+        self.modules.file_at(path)
+    }
+
+    /// Finds the `use` directive that brings `name` into `scope`: the namespace
+    /// of a qualified name like `items::Item`, a directive listing it, or else a
+    /// `use *` of a file that exports it. `location` is where `name` is written,
+    /// where a missing or private qualified name, or one more than one `use *`
+    /// brings in, is reported.
+    fn imported(
+        &self,
+        name: &str,
+        scope: &ParsedFile,
+        location: &Location,
+    ) -> Result<Option<Imported>, ConfigError> {
+        if let Some((namespace, name)) = name.split_once("::") {
+            let import = match scope.namespace(namespace) {
+                Some(import) => import,
+                None => return Err(unknown_namespace(namespace, scope, location)),
+            };
+            let file = match self.modules.imported_file(import) {
+                Some(file) => file,
+                None => return Ok(None),
+            };
+            let qualified = ImportedName {
+                name: name.to_string(),
+                local_name: name.to_string(),
+                location: location.clone(),
+            };
+            check_imported(&qualified, import, &file)?;
+            return Ok(Some(Imported {
+                file,
+                name: qualified.name,
+                location: qualified.location,
+            }));
+        }
+
+        if let Some((import, imported)) = scope.imported_name(name) {
+            return Ok(self.modules.imported_file(import).map(|file| Imported {
+                file,
+                name: imported.name.clone(),
+                location: imported.location.clone(),
+            }));
+        }
+        let mut candidates: Vec<(&Import, Rc<ParsedFile>)> = vec![];
+        for import in scope.imports.iter().filter(|import| import.wildcard) {
+            let file = match self.modules.imported_file(import) {
+                Some(file) => file,
+                None => continue,
+            };
+            let exported = file.declares(name) && file.exports_name(name);
+            if exported && !candidates.iter().any(|(_, other)| Rc::ptr_eq(other, &file)) {
+                candidates.push((import, file));
+            }
+        }
+        if candidates.len() > 1 {
+            return Err(ConfigError::AmbiguousName {
+                name: name.to_string(),
+                paths: candidates
+                    .iter()
+                    .map(|(import, _)| import.path.clone())
+                    .collect(),
+                location: location.clone(),
+                imported_at: candidates
+                    .iter()
+                    .map(|(import, _)| import.location.clone())
+                    .collect(),
+            });
+        }
+        Ok(candidates.pop().map(|(import, file)| Imported {
+            file,
+            name: name.to_string(),
+            location: import.location.clone(),
+        }))
+    }
+
+    /// Looks up `requested` in `scope`, the file it's named in. `chain` holds the
+    /// types whose parents are being looked up, to catch a type inheriting from itself.
+    fn resolve_type(
+        &mut self,
+        requested: &Type,
+        scope: &ParsedFile,
+        chain: &mut Vec<String>,
+    ) -> Result<Type, ConfigError> {
+        let declared = scope
+            .user_types
+            .iter()
+            .find(|usertype| usertype.typename == requested.typename);
+        let declared = match declared {
+            Some(declared) => declared,
+            None => {
+                let imported =
+                    match self.imported(&requested.typename, scope, &requested.location)? {
+                        Some(imported) => imported,
+                        None => return Err(unknown_type(requested, &scope.user_types)),
+                    };
+                // A type missing from the imported file is reported at the `use` directive.
+                let requested = Type {
+                    typename: imported.name,
+                    location: imported.location,
+                    ..Type::new()
+                };
+                return self.resolve_type(&requested, &imported.file, chain);
+            }
+        };
+
+        let key = (scope.path.clone(), declared.typename.clone());
+        if let Some(resolved) = self.types.get(&key) {
+            return Ok(resolved.clone());
+        }
+
+        let mut resolved = declared.clone();
+        if !declared.parents.is_empty() {
+            chain.push(declared.typename.clone());
+            let mut parents = vec![];
+            for parent in &declared.parents {
+                if chain.contains(&parent.typename) {
+                    chain.push(parent.typename.clone());
+                    return Err(ConfigError::InheritanceCycle {
+                        chain: chain.clone(),
+                        location: parent.location.clone(),
+                    });
+                }
+                parents.push(as_parent(&self.resolve_type(parent, scope, chain)?));
+            }
+            chain.pop();
+
+            let inherited = merge_parents(
+                parents
+                    .iter()
+                    .map(|parent| (&parent.typename, &parent.fields[..])),
+                &declared.fields,
+                &declared.typename,
+                &declared.location,
+            )?;
+            resolved.fields = inherit(inherited, declared)?;
+            resolved.parents = parents;
+        }
+        self.types.insert(key, resolved.clone());
+        Ok(resolved)
+    }
+
+    /// Applies an object's parents to it. `scope` is the file it's declared in.
+    fn resolve_object(
+        &mut self,
+        object: &Object,
+        scope: &ParsedFile,
+    ) -> Result<Object, ConfigError> {
+        let mut object = object.clone();
+        let mut types: Vec<Type> = vec![];
+        for parent in &object.parents {
+            let parent_type = if self.names_object(parent, scope)? {
+                let requested = Object {
+                    object_name: parent.typename.clone(),
+                    location: parent.location.clone(),
+                    ..Object::new()
+                };
+                let prototype = self.find_object(&requested, scope)?;
+                let prototype_type = prototype.object_type.clone();
+                object.prototypes.push(prototype);
+                prototype_type
+            } else {
+                Some(self.resolve_type(parent, scope, &mut Vec::new())?)
+            };
+            if let Some(parent_type) = parent_type {
+                if !types.contains(&parent_type) {
+                    types.push(parent_type);
+                }
+            }
+        }
+
+        if !object.prototypes.is_empty() {
+            let inherited = merge_parents(
+                object
+                    .prototypes
+                    .iter()
+                    .map(|prototype| (&prototype.object_name, &prototype.fields[..])),
+                &object.fields,
+                &object.object_name,
+                &object.location,
+            )?;
+            object.fields = merge_fields(&inherited, &object.fields);
+        }
+
+        let object_type = if types.len() > 1 {
+            Some(Type {
+                fields: merge_parents(
+                    types
+                        .iter()
+                        .map(|parent| (&parent.typename, &parent.fields[..])),
+                    &object.fields,
+                    &object.object_name,
+                    &object.location,
+                )?,
+                parents: types,
+                location: object.location.clone(),
+                ..Type::new()
+            })
+        } else {
+            types.pop()
+        };
+
+        if let Some(object_type) = &object_type {
+            self.filling.push(object_type.typename.clone());
+            let filled = self.fill_fields(
+                &mut object.fields,
+                object_type,
+                &object.object_name,
+                "",
+                &object.location,
+            );
+            self.filling.pop();
+            filled?;
+        } else {
+            self.link_fields(&mut object.fields, scope)?;
+        }
+        object.object_type = object_type;
+        Ok(object)
+    }
+
+    /// The top level field `name` refers to in `scope`, following `use`
+    /// directives, with the names in its value replaced like [`Self::link_value`] does.
+    fn find_field(
+        &mut self,
+        name: &str,
+        scope: &ParsedFile,
+        location: &Location,
+    ) -> Result<Option<Field>, ConfigError> {
+        let declared = scope
+            .user_fields
+            .iter()
+            .find(|field| field.identifier == name);
+        let declared = match declared {
+            Some(declared) => declared,
+            None => {
+                return match self.imported(name, scope, location)? {
+                    Some(imported) => self.find_field(&imported.name, &imported.file, location),
+                    None => Ok(None),
+                }
+            }
+        };
+
+        let mut field = declared.clone();
+        self.link_top_level_field(&mut field, scope, location)?;
+        Ok(Some(field))
+    }
+
+    /// Replaces the names of top level fields in the value of the top level
+    /// field `field` with their values. `location` is where `field` is referred
+    /// to from, where it's reported if its value ends up naming it.
+    fn link_top_level_field(
+        &mut self,
+        field: &mut Field,
+        scope: &ParsedFile,
+        location: &Location,
+    ) -> Result<(), ConfigError> {
+        let reference = (scope.path.clone(), field.identifier.clone());
+        if let Some(start) = self
+            .linking
+            .iter()
+            .position(|linking| *linking == reference)
+        {
+            let mut chain: Vec<String> = self.linking[start..]
+                .iter()
+                .map(|(_, name)| name.clone())
+                .collect();
+            chain.push(field.identifier.clone());
+            return Err(ConfigError::ReferenceCycle {
+                chain,
+                location: location.clone(),
+            });
+        }
+        self.linking.push(reference);
+        let value_location = field.value_location.clone();
+        let linked = self.link_value(&mut field.value, scope, &value_location);
+        self.linking.pop();
+        linked
+    }
+
+    /// Replaces the names of top level fields in the values of `fields` with
+    /// the values of those fields. `scope` is the file the fields are declared in.
+    fn link_fields(&mut self, fields: &mut [Field], scope: &ParsedFile) -> Result<(), ConfigError> {
+        for field in fields {
+            let location = field.value_location.clone();
+            self.link_value(&mut field.value, scope, &location)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces a bare name in `value` that names a top level field, here or
+    /// imported, with that field's value, looking through lists, maps and
+    /// objects. Any other name is left to be an enum variant. Values with a
+    /// declared type are linked by [`Self::fill_value`] instead, which knows
+    /// when a name is a variant of that type.
+    fn link_value(
+        &mut self,
+        value: &mut Value,
+        scope: &ParsedFile,
+        location: &Location,
+    ) -> Result<(), ConfigError> {
+        match value {
+            Value::Variant { name, index: None } => {
+                if let Some(field) = self.find_field(&name.clone(), scope, location)? {
+                    *value = field.value;
+                }
+                Ok(())
+            }
+            Value::List(elements) => elements
+                .iter_mut()
+                .try_for_each(|element| self.link_value(element, scope, location)),
+            Value::Map(entries) => self.link_fields(entries, scope),
+            Value::Object(object) => self.link_fields(&mut object.fields, scope),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether `parent` names an object rather than a type. `use` directives can name either.
+    fn names_object(&mut self, parent: &Type, scope: &ParsedFile) -> Result<bool, ConfigError> {
+        let name = &parent.typename;
+        if scope
+            .user_types
+            .iter()
+            .any(|usertype| usertype.typename == *name)
+        {
+            return Ok(false);
+        }
+        if scope
+            .user_objects
+            .iter()
+            .any(|object| object.object_name == *name)
+        {
+            return Ok(true);
+        }
+        let imported = match self.imported(name, scope, &parent.location)? {
+            Some(imported) => imported,
+            None => return Ok(false),
+        };
+        let requested = Type {
+            typename: imported.name,
+            location: imported.location,
+            ..Type::new()
+        };
+        self.names_object(&requested, &imported.file)
+    }
+
+    /// The resolved object `requested` names in `scope`, following `use` directives.
+    fn find_object(
+        &mut self,
+        requested: &Object,
+        scope: &ParsedFile,
+    ) -> Result<Rc<Object>, ConfigError> {
+        let declared = scope
+            .user_objects
+            .iter()
+            .position(|object| object.object_name == requested.object_name);
+        if let Some(position) = declared {
+            return self.resolved_object(position, scope);
+        }
+        let imported = match self.imported(&requested.object_name, scope, &requested.location)? {
+            Some(imported) => imported,
+            None => {
+                return Err(ConfigError::UnknownType {
+                    typename: requested.object_name.clone(),
+                    location: requested.location.clone(),
+                    suggestion: None,
+                })
+            }
+        };
+        let requested = Object {
+            object_name: imported.name,
+            location: imported.location,
+            ..Object::new()
+        };
+        self.find_object(&requested, &imported.file)
+    }
+
+    /// The object at `position` among the objects of `scope`, resolved the first
+    /// time it's asked for.
+    fn resolved_object(
+        &mut self,
+        position: usize,
+        scope: &ParsedFile,
+    ) -> Result<Rc<Object>, ConfigError> {
+        let key = (scope.path.clone(), position);
+        if let Some(resolved) = self.objects.get(&key) {
+            return Ok(Rc::clone(resolved));
+        }
+        let resolved = Rc::new(self.resolve_object(&scope.user_objects[position], scope)?);
+        self.objects.insert(key, Rc::clone(&resolved));
+        Ok(resolved)
+    }
+
+    /// Checks that `fields` implements everything `object_type` requires and adds
+    /// the type's default for every field that isn't there. `prefix` is the path
+    /// from the top level object down to these fields, e.g. `weapon.`.
+    fn fill_fields(
+        &mut self,
+        fields: &mut Vec<Field>,
+        object_type: &Type,
+        object_name: &str,
+        prefix: &str,
+        location: &Location,
+    ) -> Result<(), ConfigError> {
+        // Fields the object sets that its type doesn't declare, likely typos
+        // of a field that then shows up as missing.
+        let undeclared_fields: Vec<Field> = fields
+            .iter()
+            .filter(|object_field| {
+                !object_type
+                    .fields
+                    .iter()
+                    .any(|type_field| type_field.identifier == object_field.identifier)
+            })
+            .cloned()
+            .collect();
+
+        for type_field in &object_type.fields {
+            let path = format!("{}{}", prefix, type_field.identifier);
+            let object_field = fields
+                .iter_mut()
+                .find(|object_field| object_field.identifier == type_field.identifier);
+            if let Some(object_field) = object_field {
+                let value_location = object_field.value_location.clone();
+                self.fill_value(
+                    &mut object_field.value,
+                    &type_field.identifier_type,
+                    type_field,
+                    &value_location,
+                    object_name,
+                    &path,
+                )?;
+                continue;
+            }
+
+            let mut default = type_field.clone();
+            let field_type = &type_field.identifier_type;
+            if !type_field.initialized && field_type.optional {
+                // Left out, so it comes out as `null`.
+                fields.push(default);
+                continue;
+            }
+            if !type_field.initialized {
+                // A field of a user defined type without a default takes the defaults of
+                // that type. An enum has no defaults to take.
+                let takes_defaults = !field_type.typename.is_empty()
+                    && !field_type.is_builtin()
+                    && !self.filling.contains(&field_type.typename)
+                    && !self.find_type(field_type)?.is_enum();
+                if !takes_defaults {
+                    let suggestion = closest_match(
+                        &type_field.identifier,
+                        undeclared_fields
+                            .iter()
+                            .map(|field| field.identifier.as_str()),
+                    )
+                    .and_then(|name| {
+                        undeclared_fields
+                            .iter()
+                            .find(|field| field.identifier == name)
+                    })
+                    .map(|field| Suggestion {
+                        name: field.identifier.clone(),
+                        location: field.location.clone(),
+                    });
+                    return Err(ConfigError::MissingField {
+                        field: path,
+                        object: object_name.to_string(),
+                        location: location.clone(),
+                        declared_at: type_field.location.clone(),
+                        suggestion,
+                    });
+                }
+                default.value = Value::Map(Vec::new());
+                default.value_location = location.clone();
+                default.initialized = true;
+            }
+            let value_location = default.value_location.clone();
+            self.fill_value(
+                &mut default.value,
+                field_type,
+                type_field,
+                &value_location,
+                object_name,
+                &path,
+            )?;
+            fields.push(default);
+        }
+
+        // What the type doesn't declare isn't filled, but can still name top level fields.
+        for field in fields.iter_mut() {
+            let declared = object_type
+                .fields
+                .iter()
+                .any(|type_field| type_field.identifier == field.identifier);
+            if !declared {
+                let scope = self.file_at(&field.value_location.path);
+                let location = field.value_location.clone();
+                self.link_value(&mut field.value, &scope, &location)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the defaults `usertype` gives its fields the way values set by an
+    /// object are checked, without keeping the filled in values.
+    fn check_defaults(&mut self, usertype: &Type) -> Result<(), ConfigError> {
+        self.filling.push(usertype.typename.clone());
+        let checked = usertype
+            .fields
+            .iter()
+            .filter(|field| field.initialized)
+            .try_for_each(|field| {
+                self.fill_value(
+                    &mut field.value.clone(),
+                    &field.identifier_type,
+                    field,
+                    &field.value_location,
+                    &usertype.typename,
+                    &field.identifier,
+                )
+            });
+        self.filling.pop();
+        checked
+    }
+
+    /// Fills in the defaults of every value of a user defined type in `value`,
+    /// looking through lists and maps, and checks the constraints of its type.
+    fn fill_value(
+        &mut self,
+        value: &mut Value,
+        expected: &Type,
+        declaration: &Field,
+        location: &Location,
+        object_name: &str,
+        path: &str,
+    ) -> Result<(), ConfigError> {
+        if let Value::Variant { name, index: None } = value {
+            if !self.has_variant(expected, name)? {
+                let scope = self.file_at(&location.path);
+                if let Some(field) = self.find_field(&name.clone(), &scope, location)? {
+                    *value = field.value;
+                }
+            }
+        }
+        if expected.optional && *value == Value::Null {
+            return Ok(());
+        }
+        if !expected.parameters.is_empty() {
+            check_value(value, expected, declaration, location)?;
+        }
+        check_constraints(value, expected, location, object_name, path)?;
+
+        match (expected.typename.as_str(), value) {
+            ("list", Value::List(elements)) => {
+                if let Some(element_type) = expected.parameters.first() {
+                    for (index, element) in elements.iter_mut().enumerate() {
+                        let element_path = format!("{}[{}]", path, index);
+                        self.fill_value(
+                            element,
+                            element_type,
+                            declaration,
+                            location,
+                            object_name,
+                            &element_path,
+                        )?;
+                    }
+                }
+            }
+            ("map", Value::Map(entries)) => {
+                if let Some(value_type) = expected.parameters.get(1) {
+                    for entry in entries.iter_mut() {
+                        let entry_path = format!("{}.{}", path, entry.identifier);
+                        let entry_location = entry.value_location.clone();
+                        self.fill_value(
+                            &mut entry.value,
+                            value_type,
+                            declaration,
+                            &entry_location,
+                            object_name,
+                            &entry_path,
+                        )?;
+                    }
+                }
+            }
+            (_, value) if !expected.typename.is_empty() && !expected.is_builtin() => {
+                let user_type = self.find_type(expected)?;
+                if let Some(variants) = &user_type.variants {
+                    return select_variant(value, &user_type, variants, declaration, location);
+                }
+                let mut nested = match value {
+                    Value::Object(object) => (**object).clone(),
+                    Value::Map(entries) => Object {
+                        object_name: declaration.identifier.clone(),
+                        fields: entries.clone(),
+                        location: location.clone(),
+                        ..Object::new()
+                    },
+                    _ => {
+                        return Err(ConfigError::TypeMismatch {
+                            field: declaration.identifier.clone(),
+                            expected: expected.to_string(),
+                            found: value.literal_type().to_string(),
+                            location: location.clone(),
+                            declared_at: declaration.location.clone(),
+                        })
+                    }
+                };
+
+                self.filling.push(user_type.typename.clone());
+                let filled = self.fill_fields(
+                    &mut nested.fields,
+                    &user_type,
+                    object_name,
+                    &format!("{}.", path),
+                    location,
+                );
+                self.filling.pop();
+                filled?;
+
+                nested.object_type = Some(user_type);
+                *value = Value::Object(Box::new(nested));
+            }
+            (_, value) => {
+                // Nothing says what the names inside are, e.g. a map set on an untyped field.
+                let scope = self.file_at(&location.path);
+                self.link_value(value, &scope, location)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `expected` is an enum with a variant called `name`.
+    fn has_variant(&mut self, expected: &Type, name: &str) -> Result<bool, ConfigError> {
+        if expected.typename.is_empty() || expected.is_builtin() {
+            return Ok(false);
+        }
+        let variants = self.find_type(expected)?.variants.unwrap_or_default();
+        Ok(variants.iter().any(|variant| variant.name == name))
+    }
+}
+
+/// Checks that `value` is one of the variants of `enumtype` and records its position.
+#[allow(clippy::result_large_err)]
+fn select_variant(
+    value: &mut Value,
+    enumtype: &Type,
+    variants: &[Variant],
+    declaration: &Field,
+    location: &Location,
+) -> Result<(), ConfigError> {
+    let (name, index) = match value {
+        Value::Variant { name, index } => (name, index),
+        _ => {
+            return Err(ConfigError::TypeMismatch {
+                field: declaration.identifier.clone(),
+                expected: enumtype.to_string(),
+                found: value.literal_type().to_string(),
+                location: location.clone(),
+                declared_at: declaration.location.clone(),
+            })
+        }
+    };
+    if let Some(position) = variants.iter().position(|variant| variant.name == *name) {
+        *index = Some(position);
+        return Ok(());
+    }
+    let suggestion = closest_match(name, variants.iter().map(|variant| variant.name.as_str()))
+        .and_then(|closest| variants.iter().find(|variant| variant.name == closest))
+        .map(|variant| Suggestion {
+            name: variant.name.clone(),
+            location: variant.location.clone(),
+        });
+    Err(ConfigError::UnknownVariant {
+        variant: name.clone(),
+        enum_name: enumtype.typename.clone(),
+        variants: variants
+            .iter()
+            .map(|variant| variant.name.clone())
+            .collect(),
+        location: location.clone(),
+        suggestion,
+    })
+}
+
+/// `parent`, resolved, the way a type inheriting from it keeps it: with the names
+/// of its own ancestors flattened into its parents, each once, so that the types
+/// of a diamond don't each carry a copy of everything above them.
+fn as_parent(parent: &Type) -> Type {
+    let mut ancestors: Vec<Type> = vec![];
+    for grandparent in &parent.parents {
+        for ancestor in std::iter::once(grandparent).chain(&grandparent.parents) {
+            let known = ancestors.iter().any(|known| {
+                known.typename == ancestor.typename && known.location.path == ancestor.location.path
+            });
+            if !known {
+                ancestors.push(Type {
+                    typename: ancestor.typename.clone(),
+                    location: ancestor.location.clone(),
+                    ..Type::new()
+                });
+            }
+        }
+    }
+    Type {
+        parents: ancestors,
+        ..parent.clone()
+    }
+}
+
+/// The field values of a prototype with the ones an object sets itself on top.
+/// Nested objects are merged the same way, anything else is replaced.
+fn merge_fields(prototype: &[Field], fields: &[Field]) -> Vec<Field> {
+    let mut merged = prototype.to_vec();
+    for field in fields {
+        let inherited = match merged
+            .iter_mut()
+            .find(|inherited| inherited.identifier == field.identifier)
+        {
+            Some(inherited) => inherited,
+            None => {
+                merged.push(field.clone());
+                continue;
+            }
+        };
+        let mut overridden = field.clone();
+        if let (Value::Object(inherited_object), Value::Object(object)) =
+            (&inherited.value, &mut overridden.value)
+        {
+            object.fields = merge_fields(&inherited_object.fields, &object.fields);
+        }
+        *inherited = overridden;
+    }
+    merged
+}
+
+/// The fields of several parents, in order. A field more than one of them has
+/// takes the first default given, and must have the same type in each. Parents
+/// giving it different defaults are an error, unless `overrides` sets it.
+#[allow(clippy::result_large_err)]
+fn merge_parents<'f, I>(
+    parents: I,
+    overrides: &[Field],
+    child: &str,
+    location: &Location,
+) -> Result<Vec<Field>, ConfigError>
+where
+    I: IntoIterator<Item = (&'f String, &'f [Field])>,
+{
+    // Each field with the name of the parent it was taken from.
+    let mut merged: Vec<(&String, Field)> = vec![];
+    for (parent, fields) in parents {
+        for field in fields {
+            let (first_parent, inherited) = match merged
+                .iter_mut()
+                .find(|(_, inherited)| inherited.identifier == field.identifier)
+            {
+                Some(inherited) => inherited,
+                None => {
+                    merged.push((parent, field.clone()));
+                    continue;
+                }
+            };
+
+            let inherited_type = &inherited.identifier_type;
+            if !inherited_type.typename.is_empty()
+                && !field.identifier_type.typename.is_empty()
+                && field.identifier_type != *inherited_type
+            {
+                return Err(ConfigError::TypeMismatch {
+                    field: field.identifier.clone(),
+                    expected: inherited_type.to_string(),
+                    found: field.identifier_type.to_string(),
+                    location: field.location.clone(),
+                    declared_at: inherited.location.clone(),
+                });
+            }
+            if !field.initialized {
+                continue;
+            }
+            if !inherited.initialized {
+                *first_parent = parent;
+                *inherited = field.clone();
+                continue;
+            }
+            let overridden = overrides
+                .iter()
+                .any(|set| set.identifier == field.identifier);
+            if inherited.value != field.value && !overridden {
+                return Err(ConfigError::ConflictingDefaults {
+                    field: field.identifier.clone(),
+                    child: child.to_string(),
+                    first_parent: first_parent.clone(),
+                    first_location: inherited.value_location.clone(),
+                    second_parent: parent.clone(),
+                    second_location: field.value_location.clone(),
+                    location: location.clone(),
+                });
+            }
+        }
+    }
+    Ok(merged.into_iter().map(|(_, field)| field).collect())
+}
+
+/// The fields of `child` on top of the fields it inherits from its parents. A
+/// field the child declares again keeps its place and type, but takes the
+/// child's default.
+#[allow(clippy::result_large_err)]
+fn inherit(inherited: Vec<Field>, child: &Type) -> Result<Vec<Field>, ConfigError> {
+    let mut fields = inherited;
+    for field in &child.fields {
+        let inherited = match fields
+            .iter_mut()
+            .find(|inherited| inherited.identifier == field.identifier)
+        {
+            Some(inherited) => inherited,
+            None => {
+                fields.push(field.clone());
+                continue;
+            }
+        };
+
+        let inherited_type = &inherited.identifier_type;
+        if field.identifier_type.typename.is_empty() {
+            if field.initialized {
+                check_declared(&field.value, inherited, &field.value_location)?;
+            }
+        } else if !inherited_type.typename.is_empty() && field.identifier_type != *inherited_type {
+            return Err(ConfigError::TypeMismatch {
+                field: field.identifier.clone(),
+                expected: inherited_type.to_string(),
+                found: field.identifier_type.to_string(),
+                location: field.location.clone(),
+                declared_at: inherited.location.clone(),
+            });
+        }
+
+        let mut overridden = field.clone();
+        if overridden.identifier_type.typename.is_empty() {
+            overridden.identifier_type = inherited.identifier_type.clone();
+        }
+        if !overridden.initialized {
+            overridden.value = inherited.value.clone();
+            overridden.value_location = inherited.value_location.clone();
+            overridden.initialized = inherited.initialized;
+        }
+        if overridden.doc.is_none() {
+            overridden.doc = inherited.doc.clone();
+        }
+        *inherited = overridden;
+    }
+    Ok(fields)
+}
+
+/// The error for a type that isn't in `declared_types`, suggesting the closest one that is.
+fn unknown_type(requested: &Type, declared_types: &[Type]) -> ConfigError {
+    let suggestion = closest_match(
+        &requested.typename,
+        declared_types
+            .iter()
+            .map(|usertype| usertype.typename.as_str()),
+    )
+    .and_then(|name| {
+        declared_types
+            .iter()
+            .find(|usertype| usertype.typename == name)
+    })
+    .map(|usertype| Suggestion {
+        name: usertype.typename.clone(),
+        location: usertype.location.clone(),
+    });
+    ConfigError::UnknownType {
+        typename: requested.typename.clone(),
+        location: requested.location.clone(),
+        suggestion,
+    }
+}
+
+/// Checks if each of the user's objects implements all the necessary values of its type
+/// and expands each object's fields to have a field of its parent if that field isn't there.
+/// Fields of user defined types get the same treatment, all the way down. An object
+/// declared with another object as its parent starts from that object's resolved fields.
+/// Objects and top level fields a `use` directive lists by name are part of the result,
+/// under the names they're imported as, ahead of the file's own.
+#[allow(clippy::result_large_err)]
+pub(crate) fn fill_object_fields(
+    file: &ParsedFile,
+    options: &ResolveOptions,
+) -> Result<Document, ConfigError> {
+    let mut resolver = TypeResolver::new(ModuleGraph::load(file, options)?);
+    // Look up the parents of every type, used or not, so broken ones are always reported.
+    // Their defaults too, so a misspelled variant is caught even if no object uses the default.
+    for usertype in &file.user_types {
+        let resolved = resolver.find_type(usertype)?;
+        resolver.check_defaults(&resolved)?;
+    }
+
+    let mut document = Document {
+        fields: vec![],
+        objects: vec![],
+    };
+    for import in &file.imports {
+        let imported_file = match resolver.modules.imported_file(import) {
+            Some(imported_file) => imported_file,
+            None => continue,
+        };
+        // Only names listed by name are output. `use *` brings in types to use,
+        // not whatever sample objects a library of them happens to declare.
+        for name in import.names.clone() {
+            if let Some(mut field) =
+                resolver.find_field(&name.name, &imported_file, &name.location)?
+            {
+                field.identifier = name.local_name;
+                document.fields.push(field);
+                continue;
+            }
+            let requested = Type {
+                typename: name.name.clone(),
+                ..Type::new()
+            };
+            if resolver.names_object(&requested, &imported_file)? {
+                let requested = Object {
+                    object_name: name.name,
+                    location: name.location,
+                    ..Object::new()
+                };
+                let mut object = (*resolver.find_object(&requested, &imported_file)?).clone();
+                object.object_name = name.local_name;
+                document.objects.push(object);
+            }
+        }
+    }
+
+    for field in &file.user_fields {
+        let mut field = field.clone();
+        let location = field.location.clone();
+        resolver.link_top_level_field(&mut field, file, &location)?;
+        document.fields.push(field);
+    }
+    for position in 0..file.user_objects.len() {
+        let object = resolver.resolved_object(position, file)?;
+        document.objects.push((*object).clone());
+    }
+    Ok(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ConfigError;
+
+    fn resolve(source: &str) -> crate::Document {
+        let parsed = crate::parse_str(source).unwrap();
+        crate::resolve(&parsed).unwrap()
+    }
+
+    fn resolve_error(source: &str) -> ConfigError {
+        let parsed = crate::parse_str(source).unwrap();
+        crate::resolve(&parsed).unwrap_err()
+    }
+
+    #[test]
+    fn fields_of_user_types_take_the_defaults_of_their_type() {
+        let document = resolve(
+            "type Gem { color = \"red\" }\ntype Weapon { damage: number = 1, gem: Gem }\n\
+             type Monster { weapon: Weapon, spare: Weapon }\nGoblin : Monster { weapon { damage = 5 } }\n",
+        );
+        assert_eq!(
+            crate::to_json(&document),
+            r#"{"Goblin":{"weapon":{"damage":5,"gem":{"color":"red"}},"spare":{"damage":1,"gem":{"color":"red"}}}}"#
+        );
+    }
+
+    #[test]
+    fn missing_nested_field_is_reported_with_its_path() {
+        let source = "type W { dmg: number, label = \"s\" }\ntype M { w: W }\n";
+        for object in ["G : M { }\n", "G : M { w { label = \"x\" } }\n"] {
+            match resolve_error(&format!("{}{}", source, object)) {
+                ConfigError::MissingField { field, object, .. } => {
+                    assert_eq!(field, "w.dmg");
+                    assert_eq!(object, "G");
+                }
+                error => panic!("unexpected error: {}", error),
+            }
+        }
+    }
+
+    #[test]
+    fn long_chain_of_objects_resolves_each_once() {
+        let mut source = String::from("type T { life: number = 1 }\nO0 : T { }\n");
+        for i in 1..2000 {
+            source.push_str(&format!("O{} : O{} {{ life = {} }}\n", i, i - 1, i));
+        }
+        let document = resolve(&source);
+        let last = document.objects.last().unwrap();
+        assert_eq!(last.object_name, "O1999");
+        assert_eq!(last.inheritance_chain().len(), 2000);
+    }
+
+    #[test]
+    fn layered_diamonds_of_objects_resolve_each_once() {
+        let mut source = String::from("A0 { a0 = 0 }\nA1 { a1 = 1 }\n");
+        for i in 2..60 {
+            source.push_str(&format!(
+                "A{} : A{} + A{} {{ a{} = {} }}\n",
+                i,
+                i - 1,
+                i - 2,
+                i,
+                i
+            ));
+        }
+        let document = resolve(&source);
+        let last = document.objects.last().unwrap();
+        assert_eq!(last.fields.len(), 60);
+        assert_eq!(last.inheritance_chain().len(), 59);
+    }
+
+    #[test]
+    fn layered_diamonds_of_types_resolve_each_once() {
+        let mut source = String::from("type T0 { t0: number = 0 }\ntype T1 { t1: number = 1 }\n");
+        for i in 2..60 {
+            source.push_str(&format!(
+                "type T{} : T{} + T{} {{ t{}: number = {} }}\n",
+                i,
+                i - 1,
+                i - 2,
+                i,
+                i
+            ));
+        }
+        source.push_str("O : T59 { }\n");
+        let document = resolve(&source);
+        let object = &document.objects[0];
+        assert_eq!(object.fields.len(), 60);
+        assert_eq!(object.inheritance_chain().len(), 60);
+    }
+
+    #[test]
+    fn unset_enum_field_is_missing() {
+        let error =
+            resolve_error("enum Element { Fire, Water }\ntype M { element: Element }\nA : M { }\n");
+        match error {
+            ConfigError::MissingField { field, object, .. } => {
+                assert_eq!(field, "element");
+                assert_eq!(object, "A");
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    fn variant_wins_over_top_level_field_of_the_same_name() {
+        let document = resolve(
+            "enum Element { Fire, Water }\nFire = \"hot\"\nvolume = 10\n\
+             type Spell { element: Element = Water, power: number = 1 }\n\
+             s : Spell { element = Fire, power = volume }\nplain { a = Fire }\n",
+        );
+        let json = crate::to_json(&document);
+        assert!(
+            json.contains(r#""s":{"element":"Fire","power":10}"#),
+            "{}",
+            json
+        );
+        assert!(json.contains(r#""plain":{"a":"hot"}"#), "{}", json);
+    }
+
+    #[test]
+    fn only_names_listed_by_name_are_output() {
+        let dir = std::env::temp_dir().join("config_file_listed_output");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("lib.cfg"),
+            "type T { life: number = 1 }\nsample : T { }\nGoblin : T { life = 5 }\n",
+        )
+        .unwrap();
+        let main = dir.join("main.cfg");
+        std::fs::write(
+            &main,
+            "use * from \"lib.cfg\"\nuse Goblin from \"lib.cfg\"\nHob : sample { }\n",
+        )
+        .unwrap();
+        let document = crate::resolve(&crate::parse_file(&main).unwrap()).unwrap();
+        let names: Vec<&str> = document
+            .objects
+            .iter()
+            .map(|object| object.object_name.as_str())
+            .collect();
+        assert_eq!(names, ["Goblin", "Hob"]);
+    }
+}
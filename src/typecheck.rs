@@ -0,0 +1,389 @@
+use crate::ast::{Constraint, Field, Object, Type, Value};
+use crate::error::{ConfigError, Location};
+
+/// Checks a value against the type `declaration` gives its field, down to the
+/// elements of lists and maps. `location` is where the value was written.
+#[allow(clippy::result_large_err)]
+pub(crate) fn check_value(
+    value: &Value,
+    expected: &Type,
+    declaration: &Field,
+    location: &Location,
+) -> Result<(), ConfigError> {
+    // Untyped fields accept anything, optional ones `null` too. Values of user
+    // defined types are checked field by field when they're resolved, and names
+    // are replaced by the fields they refer to.
+    if expected.typename.is_empty()
+        || !expected.is_builtin()
+        || (expected.optional && *value == Value::Null)
+        || matches!(value, Value::Variant { index: None, .. })
+    {
+        return Ok(());
+    }
+
+    let mismatch = |found: &Type| {
+        Err(ConfigError::TypeMismatch {
+            field: declaration.identifier.clone(),
+            expected: expected.to_string(),
+            found: found.to_string(),
+            location: location.clone(),
+            declared_at: declaration.location.clone(),
+        })
+    };
+
+    match value {
+        Value::List(elements) => {
+            if expected.typename != "list" {
+                return mismatch(&value.literal_type());
+            }
+            if let Some(element_type) = expected.parameters.first() {
+                for element in elements {
+                    check_value(element, element_type, declaration, location)?;
+                }
+            }
+            Ok(())
+        }
+        Value::Map(_) | Value::Object(_) => {
+            if expected.typename != "map" {
+                return mismatch(&value.literal_type());
+            }
+            if let (Some(value_type), Some(entries)) = (expected.parameters.get(1), value.entries())
+            {
+                for entry in entries {
+                    check_value(&entry.value, value_type, declaration, &entry.value_location)?;
+                }
+            }
+            Ok(())
+        }
+        _ => {
+            let found = value.literal_type();
+            if found.typename != expected.typename {
+                return mismatch(&found);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Checks a value against the constraints of its type, like the range in
+/// `number(0..=1000)`. Values of the wrong type are left to [`check_value`].
+/// `object` and `path` name the field in the error.
+#[allow(clippy::result_large_err)]
+pub(crate) fn check_constraints(
+    value: &Value,
+    expected: &Type,
+    location: &Location,
+    object: &str,
+    path: &str,
+) -> Result<(), ConfigError> {
+    for constraint in &expected.constraints {
+        let violation = match (constraint, value) {
+            (Constraint::Range(bounds), _) => match value.as_number() {
+                Some(number) if !bounds.contains(number) => {
+                    Some((format!("be in {}", bounds), number.to_string()))
+                }
+                _ => None,
+            },
+            (Constraint::Length(bounds), _) => {
+                let length = match value {
+                    Value::String(string) => Some(string.chars().count()),
+                    Value::List(elements) => Some(elements.len()),
+                    _ => value.entries().map(|entries| entries.len()),
+                };
+                match length {
+                    Some(length) if !bounds.contains(length as f64) => Some((
+                        format!("have a length in {}", bounds),
+                        format!("a length of {}", length),
+                    )),
+                    _ => None,
+                }
+            }
+            (Constraint::Pattern(pattern), Value::String(string)) => {
+                match regex::Regex::new(pattern) {
+                    Ok(regex) if !regex.is_match(string) => {
+                        Some((format!("match {:?}", pattern), format!("{:?}", string)))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if let Some((requirement, found)) = violation {
+            return Err(ConfigError::ConstraintViolation {
+                field: path.to_string(),
+                object: object.to_string(),
+                requirement,
+                found,
+                location: location.clone(),
+                declared_at: expected.location.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The type values of a field must have: the one it's declared with, or,
+/// for an untyped field with a default, the type of that default.
+fn declared_type(declaration: &Field) -> Type {
+    if !declaration.identifier_type.typename.is_empty() || !declaration.initialized {
+        return declaration.identifier_type.clone();
+    }
+    // Only the outer kind of a default is inferred. `stats = { hp = 1 }`
+    // doesn't stop an object from adding fields of other types to `stats`.
+    let found = declaration.value.literal_type();
+    Type {
+        typename: found.typename,
+        ..Type::new()
+    }
+}
+
+/// Checks a value set on a field against the type `declaration` gives it with
+/// [`declared_type`]. A default inferred that way can always be cleared with `null`.
+#[allow(clippy::result_large_err)]
+pub(crate) fn check_declared(
+    value: &Value,
+    declaration: &Field,
+    location: &Location,
+) -> Result<(), ConfigError> {
+    if *value == Value::Null && declaration.identifier_type.typename.is_empty() {
+        return Ok(());
+    }
+    check_value(value, &declared_type(declaration), declaration, location)
+}
+
+/// Checks every field an object sets against the field its type declares,
+/// then the objects nested in its fields.
+#[allow(clippy::result_large_err)]
+fn check_object(object: &Object) -> Result<(), ConfigError> {
+    let object_type = match &object.object_type {
+        Some(object_type) => object_type,
+        None => return Ok(()),
+    };
+    for field in &object.fields {
+        let declaration = object_type
+            .fields
+            .iter()
+            .find(|declaration| declaration.identifier == field.identifier);
+        if let Some(declaration) = declaration {
+            check_declared(&field.value, declaration, &field.value_location)?;
+        }
+        check_nested_objects(&field.value)?;
+    }
+    Ok(())
+}
+
+#[allow(clippy::result_large_err)]
+fn check_nested_objects(value: &Value) -> Result<(), ConfigError> {
+    match value {
+        Value::List(elements) => elements.iter().try_for_each(check_nested_objects),
+        Value::Map(entries) => entries
+            .iter()
+            .try_for_each(|entry| check_nested_objects(&entry.value)),
+        Value::Object(object) => check_object(object),
+        _ => Ok(()),
+    }
+}
+
+/// Reports a variant name in `value` that no enum type was found for.
+#[allow(clippy::result_large_err)]
+fn check_variants(value: &Value, location: &Location) -> Result<(), ConfigError> {
+    match value {
+        Value::Variant { name, index: None } => Err(ConfigError::UntypedVariant {
+            variant: name.clone(),
+            location: location.clone(),
+        }),
+        Value::List(elements) => elements
+            .iter()
+            .try_for_each(|element| check_variants(element, location)),
+        Value::Map(_) | Value::Object(_) => value
+            .entries()
+            .unwrap_or_default()
+            .iter()
+            .try_for_each(|entry| check_variants(&entry.value, &entry.value_location)),
+        _ => Ok(()),
+    }
+}
+
+/// Checks the fields of resolved objects against the types their object's type
+/// declares, reporting a mismatch at the value with a note at the declaration.
+#[allow(clippy::result_large_err)]
+/// Variant names are only allowed where an enum type says which enum they're from.
+pub(crate) fn check_document(fields: &[Field], objects: &[Object]) -> Result<(), ConfigError> {
+    objects.iter().try_for_each(check_object)?;
+    let object_fields = objects.iter().flat_map(|object| object.fields.iter());
+    fields
+        .iter()
+        .chain(object_fields)
+        .try_for_each(|field| check_variants(&field.value, &field.value_location))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ConfigError;
+
+    fn resolve_error(source: &str) -> ConfigError {
+        let parsed = crate::parse_str(source).unwrap();
+        crate::resolve(&parsed).unwrap_err()
+    }
+
+    fn mismatch(source: &str) -> (String, String, String) {
+        match resolve_error(source) {
+            ConfigError::TypeMismatch {
+                field,
+                expected,
+                found,
+                ..
+            } => (field, expected, found),
+            error => panic!("expected a type mismatch, got {:?}", error),
+        }
+    }
+
+    fn mismatch_of(field: &str, expected: &str, found: &str) -> (String, String, String) {
+        (field.to_string(), expected.to_string(), found.to_string())
+    }
+
+    #[test]
+    fn list_elements_must_have_the_element_type() {
+        assert_eq!(
+            mismatch("type M { drops: list<string> = [] }\nG : M { drops = [\"bone\", 2] }\n"),
+            mismatch_of("drops", "string", "number")
+        );
+        assert_eq!(
+            mismatch("type M { grid: list<list<number>> = [] }\nG : M { grid = [[1], [\"x\"]] }\n"),
+            mismatch_of("grid", "number", "string")
+        );
+    }
+
+    #[test]
+    fn map_values_must_have_the_value_type() {
+        assert_eq!(
+            mismatch(
+                "type M { r: map<string, number> = {} }\nG : M { r = { fire = 2, ice = \"cold\" } }\n"
+            ),
+            mismatch_of("r", "number", "string")
+        );
+    }
+
+    #[test]
+    fn map_keys_must_be_strings() {
+        let errors = crate::parse_partial("type M { r: map<number, number> = {} }\n", "test.cfg").1;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message(),
+            "expected 'string' as the key type, found 'number'"
+        );
+    }
+
+    #[test]
+    fn values_must_have_the_declared_or_inferred_type() {
+        let declared = "type M { life: number = 1, name = \"x\" }\nG : M { life = \"lots\" }\n";
+        assert_eq!(mismatch(declared), mismatch_of("life", "number", "string"));
+        let inferred = "type M { life: number = 1, name = \"x\" }\nG : M { name = 3 }\n";
+        assert_eq!(mismatch(inferred), mismatch_of("name", "string", "number"));
+    }
+
+    fn violation(source: &str) -> String {
+        match resolve_error(source) {
+            error @ ConfigError::ConstraintViolation { .. } => error.message(),
+            error => panic!("expected a constraint violation, got {:?}", error),
+        }
+    }
+
+    fn parse_error(source: &str) -> String {
+        let errors = crate::parse_partial(source, "test.cfg").1;
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        errors[0].message()
+    }
+
+    #[test]
+    fn numbers_must_be_within_their_range() {
+        assert_eq!(
+            violation("type M { life: number(0..=1000) = 10 }\nH : M { life = 1000 }\nG : M { life = 1001 }\n"),
+            "field 'life' of 'G' must be in 0..=1000, found 1001"
+        );
+        assert_eq!(
+            violation("type M { life: number(0..1000) = 10 }\nG : M { life = 1000 }\n"),
+            "field 'life' of 'G' must be in 0..1000, found 1000"
+        );
+    }
+
+    #[test]
+    fn ranges_can_be_open_ended() {
+        assert_eq!(
+            violation(
+                "type M { life: number(..5) = 1 }\nG : M { life = -100 }\nH : M { life = 5 }\n"
+            ),
+            "field 'life' of 'H' must be in ..5, found 5"
+        );
+        assert_eq!(
+            violation(
+                "type M { life: number(1..) = 1 }\nG : M { life = 100000 }\nH : M { life = 0 }\n"
+            ),
+            "field 'life' of 'H' must be in 1.., found 0"
+        );
+    }
+
+    #[test]
+    fn strings_must_have_their_length_and_pattern() {
+        assert_eq!(
+            violation("type M { name: string(len 1..4) = \"ab\" }\nG : M { name = \"abcd\" }\n"),
+            "field 'name' of 'G' must have a length in 1..4, found a length of 4"
+        );
+        assert_eq!(
+            violation(
+                "type M { tags: list<string>(len ..2) = [] }\nG : M { tags = [\"a\", \"b\"] }\n"
+            ),
+            "field 'tags' of 'G' must have a length in ..2, found a length of 2"
+        );
+        assert_eq!(
+            violation(
+                "type M { id: string(match \"^[a-z_]+$\") = \"a\" }\nG : M { id = \"Bad\" }\n"
+            ),
+            "field 'id' of 'G' must match \"^[a-z_]+$\", found \"Bad\""
+        );
+    }
+
+    #[test]
+    fn defaults_must_satisfy_the_constraints_they_inherit() {
+        assert_eq!(
+            violation("type A { x: number(0..10) = 5 }\ntype B : A { x = 50 }\nO : B { }\n"),
+            "field 'x' of 'B' must be in 0..10, found 50"
+        );
+    }
+
+    #[test]
+    fn constraints_are_checked_when_parsed() {
+        assert_eq!(
+            parse_error("type M { id: string(match \"[a-\") }\n"),
+            "invalid regular expression \"[a-\""
+        );
+        assert_eq!(
+            parse_error("type M { id: number(match \"a\") }\n"),
+            "expected a constraint on 'number', found 'match \"a\"'"
+        );
+        assert_eq!(
+            parse_error("type M { id: string(0..1) }\n"),
+            "expected a constraint on 'string', found '0..1'"
+        );
+        assert_eq!(
+            parse_error("type M { id: bool(0..1) }\n"),
+            "expected a constraint on 'bool', found '0..1'"
+        );
+    }
+
+    #[test]
+    fn only_optional_and_inferred_fields_take_null() {
+        let source =
+            "type M { life: number? = 1, name = \"x\" }\nG : M { life = null, name = null }\n";
+        let parsed = crate::parse_str(source).unwrap();
+        assert!(crate::resolve(&parsed).is_ok());
+        assert_eq!(
+            mismatch("type M { life: number = 1 }\nG : M { life = null }\n"),
+            mismatch_of("life", "number", "null")
+        );
+        assert_eq!(
+            mismatch("type M { name = \"x\" }\nG : M { name = 3 }\n"),
+            mismatch_of("name", "string", "number")
+        );
+    }
+}